@@ -0,0 +1,113 @@
+use crate::connection::MyWs;
+use crate::domain::client::ClientInfo;
+use crate::domain::store::LogEntry;
+use crate::server::{ConnectionId, Server};
+use actix::{Actor, Addr, Context, Handler, Message};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Register an authenticated connection.
+pub struct Join {
+    pub connection: ConnectionId,
+    pub client: ClientInfo,
+    pub addr: Addr<MyWs>,
+}
+
+impl Message for Join {
+    type Result = ();
+}
+
+/// Unregister a closed connection.
+pub struct Leave {
+    pub connection: ConnectionId,
+}
+
+impl Message for Leave {
+    type Result = ();
+}
+
+/// Send actions just added to the log to every connection receiving them.
+pub struct Broadcast {
+    pub entries: Vec<LogEntry>,
+}
+
+impl Message for Broadcast {
+    type Result = ();
+}
+
+/// Actions a connection should send to its client in a `sync` message.
+pub struct SendSync {
+    pub entries: Vec<LogEntry>,
+}
+
+impl Message for SendSync {
+    type Result = ();
+}
+
+/// Actor knowing every live connection of the server.
+pub struct Broadcaster {
+    server: Arc<Server>,
+    sessions: HashMap<ConnectionId, (ClientInfo, Addr<MyWs>)>,
+}
+
+impl Broadcaster {
+    pub fn new(server: Arc<Server>) -> Broadcaster {
+        Broadcaster {
+            server,
+            sessions: HashMap::new(),
+        }
+    }
+}
+
+impl Actor for Broadcaster {
+    type Context = Context<Self>;
+}
+
+impl Handler<Join> for Broadcaster {
+    type Result = ();
+
+    fn handle(&mut self, msg: Join, _ctx: &mut Context<Self>) {
+        debug!(
+            "{} joined, {} connections",
+            msg.client.node_id,
+            self.sessions.len() + 1
+        );
+        self.sessions.insert(msg.connection, (msg.client, msg.addr));
+    }
+}
+
+impl Handler<Leave> for Broadcaster {
+    type Result = ();
+
+    fn handle(&mut self, msg: Leave, _ctx: &mut Context<Self>) {
+        if let Some((client, _)) = self.sessions.remove(&msg.connection) {
+            debug!(
+                "{} left, {} connections",
+                client.node_id,
+                self.sessions.len()
+            );
+        }
+    }
+}
+
+impl Handler<Broadcast> for Broadcaster {
+    type Result = ();
+
+    /// Actions are never sent back to the node which created them.
+    fn handle(&mut self, msg: Broadcast, _ctx: &mut Context<Self>) {
+        for (connection, (client, addr)) in &self.sessions {
+            let entries: Vec<LogEntry> = msg
+                .entries
+                .iter()
+                .filter(|entry| {
+                    self.server
+                        .is_receiver(client, *connection, &entry.action, &entry.meta)
+                })
+                .cloned()
+                .collect();
+            if !entries.is_empty() {
+                addr.do_send(SendSync { entries });
+            }
+        }
+    }
+}
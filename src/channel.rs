@@ -0,0 +1,120 @@
+use crate::domain::action::{Action, Meta};
+use crate::domain::client::ClientInfo;
+use futures::{future, Future};
+use std::collections::HashMap;
+
+/// Named parts of a channel, like `id` in `user/:id`.
+pub type ChannelParams = HashMap<String, String>;
+
+/// Actions with the current state of a channel, sent to new subscribers.
+pub type LoadFuture = Box<dyn Future<Item = Vec<Action>, Error = String>>;
+
+/// Channel route, like `user/:id`. Parts starting with `:` match any
+/// non-empty part of a channel name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelPattern {
+    parts: Vec<String>,
+}
+
+impl ChannelPattern {
+    pub fn new(pattern: &str) -> ChannelPattern {
+        ChannelPattern {
+            parts: pattern.split('/').map(String::from).collect(),
+        }
+    }
+
+    /// Params of a channel name, `None` if it doesn't match the pattern.
+    pub fn matches(&self, channel: &str) -> Option<ChannelParams> {
+        let parts: Vec<&str> = channel.split('/').collect();
+        if parts.len() != self.parts.len() {
+            return None;
+        }
+
+        let mut params = ChannelParams::new();
+        for (pattern, part) in self.parts.iter().zip(parts) {
+            if let Some(name) = pattern.strip_prefix(':') {
+                if part.is_empty() {
+                    return None;
+                }
+                params.insert(name.to_string(), part.to_string());
+            } else if pattern != part {
+                return None;
+            }
+        }
+        Some(params)
+    }
+}
+
+/// Callbacks for the channels matching a pattern.
+pub trait ChannelHandler: Send + Sync {
+    /// Check if the client can subscribe to the channel.
+    fn access(
+        &self,
+        client: &ClientInfo,
+        params: &ChannelParams,
+        action: &Action,
+        meta: &Meta,
+    ) -> bool;
+
+    /// Current state of the channel, sent only to the new subscriber.
+    fn load(
+        &self,
+        _client: &ClientInfo,
+        _params: &ChannelParams,
+        _action: &Action,
+        _meta: &Meta,
+    ) -> LoadFuture {
+        Box::new(future::ok(Vec::new()))
+    }
+
+    /// Check if a subscriber should receive an action sent to the channel.
+    fn filter(
+        &self,
+        _subscriber: &ClientInfo,
+        _params: &ChannelParams,
+        _action: &Action,
+        _meta: &Meta,
+    ) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_params() {
+        let pattern = ChannelPattern::new("user/:id/posts/:post");
+        let params = pattern.matches("user/10/posts/first").unwrap();
+        assert_eq!(params.len(), 2);
+        assert_eq!(params["id"], "10");
+        assert_eq!(params["post"], "first");
+    }
+
+    #[test]
+    fn match_without_params() {
+        let params = ChannelPattern::new("users").matches("users").unwrap();
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn reject_an_empty_param() {
+        assert!(ChannelPattern::new("user/:id").matches("user/").is_none());
+        assert!(ChannelPattern::new(":a/:b").matches("/b").is_none());
+    }
+
+    #[test]
+    fn reject_another_length() {
+        let pattern = ChannelPattern::new("user/:id");
+        assert!(pattern.matches("user").is_none());
+        assert!(pattern.matches("user/10/posts").is_none());
+    }
+
+    #[test]
+    fn reject_another_literal() {
+        let pattern = ChannelPattern::new("user/:id/posts");
+        assert!(pattern.matches("users/10/posts").is_none());
+        assert!(pattern.matches("user/10/comments").is_none());
+    }
+}
@@ -0,0 +1,409 @@
+use log::LevelFilter;
+use poc_logux::LoguxServerBuilder;
+use rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use rustls::NoClientAuth;
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use structopt::StructOpt;
+
+/// Command line options, every option can also be set with a `LOGUX_*`
+/// environment variable or in the configuration file.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "poc_logux", about = "Logux server")]
+pub struct Opt {
+    /// TOML configuration file, with the same keys as the options.
+    #[structopt(short, long, env = "LOGUX_CONFIG", parse(from_os_str))]
+    pub config: Option<PathBuf>,
+    #[structopt(flatten)]
+    pub settings: FileSettings,
+}
+
+// Not a doc comment, structopt would use it as the program description.
+// Settings from the command line or the configuration file, `None` when not set.
+#[derive(Debug, Default, Deserialize, StructOpt)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FileSettings {
+    /// Address to listen on [default: 127.0.0.1]
+    #[structopt(long, env = "LOGUX_HOST")]
+    pub host: Option<String>,
+    /// Port to listen on [default: 8088]
+    #[structopt(short, long, env = "LOGUX_PORT")]
+    pub port: Option<u16>,
+    /// Path of the websocket endpoint [default: /ws/]
+    #[structopt(long, env = "LOGUX_PATH")]
+    pub path: Option<String>,
+    /// One of off, error, warn, info, debug, trace [default: debug]
+    #[structopt(long, env = "LOGUX_LOG_LEVEL")]
+    pub log_level: Option<String>,
+    /// Server node id [default: random]
+    #[structopt(long, env = "LOGUX_NODE_ID")]
+    pub node_id: Option<String>,
+    /// Prefix of the random server node id, when `node-id` is not set [default: server]
+    #[structopt(long, env = "LOGUX_NODE_ID_PREFIX")]
+    pub node_id_prefix: Option<String>,
+    /// Application subprotocol version [default: 0.0.0]
+    #[structopt(long, env = "LOGUX_SUBPROTOCOL")]
+    pub subprotocol: Option<String>,
+    /// Client subprotocol versions accepted, like `>= 1.0.0` [default: *]
+    #[structopt(long, env = "LOGUX_SUPPORTS")]
+    pub supports: Option<String>,
+    /// Action log backend, only `memory` for now [default: memory]
+    #[structopt(long, env = "LOGUX_STORE")]
+    pub store: Option<String>,
+    /// PEM certificate to listen with TLS, needs `key`
+    #[structopt(long, env = "LOGUX_CERT", parse(from_os_str))]
+    pub cert: Option<PathBuf>,
+    /// PEM private key of the certificate
+    #[structopt(long, env = "LOGUX_KEY", parse(from_os_str))]
+    pub key: Option<PathBuf>,
+    /// Milliseconds without messages before pinging a client [default: 10000]
+    #[structopt(long, env = "LOGUX_PING_INTERVAL")]
+    pub ping_interval: Option<u64>,
+}
+
+impl FileSettings {
+    /// Settings of a TOML file.
+    pub fn load(file: &Path) -> Result<FileSettings, String> {
+        let content = fs::read_to_string(file)
+            .map_err(|e| format!("Can't read {}: {}", file.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Invalid {}: {}", file.display(), e))
+    }
+
+    /// Keep the settings already set, take the other ones from `other`.
+    /// `node-id` and `node-id-prefix` are one setting, both are taken from
+    /// `other` only if neither is set.
+    pub fn or(self, other: FileSettings) -> FileSettings {
+        let (node_id, node_id_prefix) = if self.node_id.is_some() || self.node_id_prefix.is_some() {
+            (self.node_id, self.node_id_prefix)
+        } else {
+            (other.node_id, other.node_id_prefix)
+        };
+        FileSettings {
+            host: self.host.or(other.host),
+            port: self.port.or(other.port),
+            path: self.path.or(other.path),
+            log_level: self.log_level.or(other.log_level),
+            node_id,
+            node_id_prefix,
+            subprotocol: self.subprotocol.or(other.subprotocol),
+            supports: self.supports.or(other.supports),
+            store: self.store.or(other.store),
+            cert: self.cert.or(other.cert),
+            key: self.key.or(other.key),
+            ping_interval: self.ping_interval.or(other.ping_interval),
+        }
+    }
+}
+
+/// Action log backends.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StoreBackend {
+    Memory,
+}
+
+/// Checked settings, ready to start the server.
+pub struct Settings {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+    pub log_level: LevelFilter,
+    pub node_id: Option<String>,
+    pub node_id_prefix: Option<String>,
+    pub subprotocol: Option<Version>,
+    pub supports: Option<VersionReq>,
+    pub store: StoreBackend,
+    pub tls: Option<rustls::ServerConfig>,
+    pub ping_interval: Option<Duration>,
+}
+
+impl Settings {
+    /// Settings from the command line and environment, then the configuration file.
+    pub fn from_args() -> Result<Settings, String> {
+        let opt = Opt::from_args();
+        let settings = match &opt.config {
+            Some(file) => opt.settings.or(FileSettings::load(file)?),
+            None => opt.settings,
+        };
+        Settings::validate(settings)
+    }
+
+    pub fn validate(settings: FileSettings) -> Result<Settings, String> {
+        let path = settings.path.unwrap_or_else(|| String::from("/ws/"));
+        if !path.starts_with('/') {
+            return Err(format!("Path {} should start with /", path));
+        }
+
+        let log_level = match settings.log_level {
+            Some(level) => level
+                .parse()
+                .map_err(|_| format!("Unknown log level {}", level))?,
+            None => LevelFilter::Debug,
+        };
+
+        if let Some(node_id) = &settings.node_id {
+            if node_id.is_empty() || node_id.contains(' ') {
+                return Err(format!("Node id \"{}\" should be a word", node_id));
+            }
+        }
+        if let Some(prefix) = &settings.node_id_prefix {
+            if settings.node_id.is_some() {
+                return Err(String::from("Node id and node id prefix can't be both set"));
+            }
+            if prefix.is_empty() || prefix.contains(' ') || prefix.contains(':') {
+                return Err(format!("Node id prefix \"{}\" should be a word", prefix));
+            }
+        }
+
+        let subprotocol = match settings.subprotocol {
+            Some(version) => Some(
+                Version::parse(&version)
+                    .map_err(|e| format!("Invalid subprotocol {}: {}", version, e))?,
+            ),
+            None => None,
+        };
+        let supports = match settings.supports {
+            Some(range) => Some(
+                VersionReq::parse(&range)
+                    .map_err(|e| format!("Invalid supported subprotocols {}: {}", range, e))?,
+            ),
+            None => None,
+        };
+
+        let store = match settings.store.as_deref() {
+            None | Some("memory") => StoreBackend::Memory,
+            Some(store) => return Err(format!("Unknown store {}", store)),
+        };
+
+        let tls = match (settings.cert, settings.key) {
+            (Some(cert), Some(key)) => Some(load_tls(&cert, &key)?),
+            (None, None) => None,
+            _ => return Err(String::from("TLS needs both a cert and a key")),
+        };
+
+        let ping_interval = match settings.ping_interval {
+            Some(0) => return Err(String::from("Ping interval should be positive")),
+            Some(ms) => Some(Duration::from_millis(ms)),
+            None => None,
+        };
+
+        Ok(Settings {
+            host: settings.host.unwrap_or_else(|| String::from("127.0.0.1")),
+            port: settings.port.unwrap_or(8088),
+            path,
+            log_level,
+            node_id: settings.node_id,
+            node_id_prefix: settings.node_id_prefix,
+            subprotocol,
+            supports,
+            store,
+            tls,
+            ping_interval,
+        })
+    }
+
+    /// Server builder with these settings.
+    pub fn apply(self, builder: LoguxServerBuilder) -> LoguxServerBuilder {
+        let mut builder = builder.host(&self.host).port(self.port).path(&self.path);
+        if let Some(node_id) = &self.node_id {
+            builder = builder.node_id(node_id);
+        }
+        if let Some(prefix) = &self.node_id_prefix {
+            builder = builder.node_id_prefix(prefix);
+        }
+        if let Some(subprotocol) = self.subprotocol {
+            builder = builder.subprotocol(subprotocol);
+        }
+        if let Some(supports) = self.supports {
+            builder = builder.supports(supports);
+        }
+        if let Some(tls) = self.tls {
+            builder = builder.tls(tls);
+        }
+        if let Some(ping_interval) = self.ping_interval {
+            builder = builder.ping_interval(ping_interval);
+        }
+        builder
+    }
+}
+
+/// TLS settings from PEM certificate and private key files.
+fn load_tls(cert: &Path, key: &Path) -> Result<rustls::ServerConfig, String> {
+    let open = |file: &Path| {
+        File::open(file)
+            .map(BufReader::new)
+            .map_err(|e| format!("Can't read {}: {}", file.display(), e))
+    };
+
+    let chain = certs(&mut open(cert)?)
+        .ok()
+        .filter(|chain| !chain.is_empty())
+        .ok_or_else(|| format!("No certificate in {}", cert.display()))?;
+    let private_key = pkcs8_private_keys(&mut open(key)?)
+        .ok()
+        .and_then(|keys| keys.into_iter().next())
+        .or_else(|| {
+            rsa_private_keys(&mut open(key).ok()?)
+                .ok()
+                .and_then(|keys| keys.into_iter().next())
+        })
+        .ok_or_else(|| format!("No private key in {}", key.display()))?;
+
+    let mut config = rustls::ServerConfig::new(NoClientAuth::new());
+    config
+        .set_single_cert(chain, private_key)
+        .map_err(|e| format!("Invalid certificate: {}", e))?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(settings: FileSettings) -> String {
+        Settings::validate(settings).err().unwrap()
+    }
+
+    #[test]
+    fn validate_defaults() {
+        let settings = Settings::validate(FileSettings::default()).unwrap();
+        assert_eq!(settings.host, "127.0.0.1");
+        assert_eq!(settings.port, 8088);
+        assert_eq!(settings.path, "/ws/");
+        assert_eq!(settings.log_level, LevelFilter::Debug);
+        assert_eq!(settings.store, StoreBackend::Memory);
+        assert!(settings.node_id.is_none());
+        assert!(settings.tls.is_none());
+        assert!(settings.ping_interval.is_none());
+    }
+
+    #[test]
+    fn validate_values() {
+        let settings = Settings::validate(FileSettings {
+            log_level: Some(String::from("warn")),
+            subprotocol: Some(String::from("1.2.0")),
+            supports: Some(String::from(">= 1.0.0")),
+            ping_interval: Some(500),
+            ..FileSettings::default()
+        })
+        .unwrap();
+        assert_eq!(settings.log_level, LevelFilter::Warn);
+        assert_eq!(settings.subprotocol, Some(Version::new(1, 2, 0)));
+        assert!(settings.supports.unwrap().matches(&Version::new(1, 1, 0)));
+        assert_eq!(settings.ping_interval, Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn validate_rejects_invalid_values() {
+        let invalid = vec![
+            FileSettings {
+                path: Some(String::from("ws")),
+                ..FileSettings::default()
+            },
+            FileSettings {
+                log_level: Some(String::from("loud")),
+                ..FileSettings::default()
+            },
+            FileSettings {
+                node_id: Some(String::from("my server")),
+                ..FileSettings::default()
+            },
+            FileSettings {
+                node_id_prefix: Some(String::from("a:b")),
+                ..FileSettings::default()
+            },
+            FileSettings {
+                subprotocol: Some(String::from("one")),
+                ..FileSettings::default()
+            },
+            FileSettings {
+                supports: Some(String::from("> one")),
+                ..FileSettings::default()
+            },
+            FileSettings {
+                store: Some(String::from("redis")),
+                ..FileSettings::default()
+            },
+            FileSettings {
+                cert: Some(PathBuf::from("cert.pem")),
+                ..FileSettings::default()
+            },
+            FileSettings {
+                ping_interval: Some(0),
+                ..FileSettings::default()
+            },
+        ];
+        for settings in invalid {
+            let description = format!("{:?}", settings);
+            assert!(
+                Settings::validate(settings).is_err(),
+                "{} should be rejected",
+                description
+            );
+        }
+    }
+
+    #[test]
+    fn validate_rejects_node_id_with_prefix() {
+        let message = error(FileSettings {
+            node_id: Some(String::from("server:1")),
+            node_id_prefix: Some(String::from("eu")),
+            ..FileSettings::default()
+        });
+        assert_eq!(message, "Node id and node id prefix can't be both set");
+    }
+
+    #[test]
+    fn command_line_wins_over_file() {
+        let command_line = FileSettings {
+            port: Some(9000),
+            ..FileSettings::default()
+        };
+        let file = FileSettings {
+            host: Some(String::from("0.0.0.0")),
+            port: Some(8443),
+            ..FileSettings::default()
+        };
+        let settings = command_line.or(file);
+        assert_eq!(settings.port, Some(9000));
+        assert_eq!(settings.host, Some(String::from("0.0.0.0")));
+    }
+
+    #[test]
+    fn node_id_and_prefix_are_merged_together() {
+        let command_line = FileSettings {
+            node_id: Some(String::from("server:1")),
+            ..FileSettings::default()
+        };
+        let file = FileSettings {
+            node_id_prefix: Some(String::from("eu")),
+            ..FileSettings::default()
+        };
+        let settings = command_line.or(file);
+        assert_eq!(settings.node_id, Some(String::from("server:1")));
+        assert_eq!(settings.node_id_prefix, None);
+        assert!(Settings::validate(settings).is_ok());
+
+        let command_line = FileSettings {
+            node_id_prefix: Some(String::from("eu")),
+            ..FileSettings::default()
+        };
+        let file = FileSettings {
+            node_id: Some(String::from("server:1")),
+            ..FileSettings::default()
+        };
+        let settings = command_line.or(file);
+        assert_eq!(settings.node_id, None);
+        assert_eq!(settings.node_id_prefix, Some(String::from("eu")));
+
+        let file = FileSettings {
+            node_id: Some(String::from("server:1")),
+            ..FileSettings::default()
+        };
+        let settings = FileSettings::default().or(file);
+        assert_eq!(settings.node_id, Some(String::from("server:1")));
+    }
+}
@@ -0,0 +1,597 @@
+use crate::broadcaster::{Broadcast, Broadcaster, Join, Leave, SendSync};
+use crate::domain::action::{Action, Meta};
+use crate::domain::client::ClientInfo;
+use crate::domain::messages::connect::ConnectMessage;
+use crate::domain::messages::connected::ConnectedMessage;
+use crate::domain::messages::debug::DebugMessage;
+use crate::domain::messages::error::LoguxError;
+use crate::domain::messages::headers::HeadersMessage;
+use crate::domain::messages::lib::LoguxEvent;
+use crate::domain::messages::message::LoguxMessage;
+use crate::domain::messages::messagesKind::MessageKind;
+use crate::domain::messages::ping::PingMessage;
+use crate::domain::messages::pong::PongMessage;
+use crate::domain::messages::sync::SyncMessage;
+use crate::domain::messages::synced::SyncedMessage;
+use crate::domain::store::LogEntry;
+use crate::domain::time::now_ms;
+use crate::handler::UndoReason;
+use crate::middleware::{
+    middleware_connect, middleware_connected, middleware_ping, middleware_pong, middleware_sync,
+};
+use crate::server::{AuthFuture, ConnectionId, Environment, Server};
+use actix::fut::{self, ActorFuture};
+use actix::{Actor, ActorContext, Addr, AsyncContext, Handler, StreamHandler};
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use futures::future;
+use semver::Version;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Check `connect` credentials with the server authenticator, then answer
+/// with `connected` or close the connection.
+fn authenticate(
+    act: &mut MyWs,
+    ctx: &mut ws::WebsocketContext<MyWs>,
+    msg: ConnectMessage,
+    receive_date: u64,
+) {
+    let client = ClientInfo::new(
+        &msg.node_id,
+        act.subprotocol
+            .clone()
+            .unwrap_or_else(|| Version::new(0, 0, 0)),
+        act.remote_address.clone(),
+        act.headers.clone(),
+    );
+    let synced = msg.synced;
+    let credentials = msg.options.and_then(|options| options.credentials);
+    act.state = ConnectionState::Authenticating;
+    let auth: AuthFuture = match &act.server.config.authenticator {
+        Some(authenticator) => {
+            authenticator(client.user_id.as_deref(), credentials.as_ref(), &client)
+        }
+        None => Box::new(future::ok(true)),
+    };
+
+    ctx.spawn(
+        fut::wrap_future(auth).then(move |result, act: &mut MyWs, ctx| {
+            match result {
+                Ok(true) => {
+                    info!("Client {} authenticated", client.node_id);
+                    act.client = Some(ClientInfo {
+                        // Headers may have changed during authentication.
+                        headers: act.headers.clone(),
+                        ..client
+                    });
+                    act.state = ConnectionState::Authenticated;
+                    act.broadcaster.do_send(Join {
+                        connection: act.connection_id,
+                        client: act.client.clone().unwrap(),
+                        addr: ctx.address(),
+                    });
+
+                    let config = &act.server.config;
+                    ctx.text(
+                        LoguxMessage::Connected(ConnectedMessage {
+                            protocol: config.protocol,
+                            time_sync: [receive_date, now_ms()],
+                            node_id: config.node_id.clone(),
+                            options: Some(config.connected_options()),
+                        })
+                        .encode(),
+                    );
+                    if !config.headers.is_empty() {
+                        let headers = config.headers.clone();
+                        act.send_headers(ctx, headers);
+                    }
+                    act.resend(ctx, synced);
+                }
+                Ok(false) => {
+                    warn!("Wrong credentials from {}", client.node_id);
+                    ctx.text(LoguxMessage::Error(LoguxError::WrongCredentials).encode());
+                    act.close(ctx);
+                }
+                Err(e) => {
+                    error!("Authentication of {} failed: {}", client.node_id, e);
+                    act.send_debug_error(ctx, &e);
+                    act.close(ctx);
+                }
+            }
+            fut::ok(())
+        }),
+    );
+}
+
+#[allow(clippy::cognitive_complexity)]
+fn process_action(
+    act: &mut MyWs,
+    message: LoguxMessage,
+    ctx: &mut ws::WebsocketContext<MyWs>,
+) -> Option<LoguxMessage> {
+    match message {
+        LoguxMessage::Error(e) => {
+            error!("Error message received: {}", e);
+            None
+        }
+
+        // After a connected is received, check if client got right to connect
+        LoguxMessage::Connect(val) => {
+            info!("Connect message received from {}", val.node_id);
+            let receive_date = now_ms();
+
+            let config = &act.server.config;
+            if !config.supports_protocol(val.protocol) {
+                warn!("Client protocol {} is not supported", val.protocol);
+                ctx.text(
+                    LoguxMessage::Error(LoguxError::WrongProtocol {
+                        supported: config.min_protocol,
+                        used: val.protocol,
+                    })
+                    .encode(),
+                );
+                act.close(ctx);
+                return None;
+            }
+            let subprotocol = val
+                .options
+                .as_ref()
+                .and_then(|options| options.subprotocol.as_ref());
+            match config.supports_subprotocol(subprotocol.map(String::as_str)) {
+                Some(version) => act.subprotocol = Some(version),
+                None => {
+                    warn!("Client subprotocol {:?} is not supported", subprotocol);
+                    ctx.text(
+                        LoguxMessage::Error(LoguxError::WrongSubprotocol {
+                            supported: config.supports.to_string(),
+                            used: subprotocol.cloned().unwrap_or_default(),
+                        })
+                        .encode(),
+                    );
+                    act.close(ctx);
+                    return None;
+                }
+            }
+            middleware_connect(ctx, &val);
+            authenticate(act, ctx, val, receive_date);
+            None
+        }
+
+        LoguxMessage::Connected(val) => {
+            info!("Connected message received from {}", val.node_id);
+            middleware_connected(ctx, &val);
+            None
+        }
+
+        // After a ping message is received by the server, send back a pong message.
+        LoguxMessage::Ping(val) => {
+            info!("Ping message received: {}", val.synced);
+            middleware_ping(ctx, &val);
+            Some(LoguxMessage::Pong(PongMessage { synced: val.synced }))
+        }
+
+        // After a pong message is received by the client, keep the connection on.
+        LoguxMessage::Pong(val) => {
+            info!("Pong message received: {}", val.synced);
+            act.ping_sent = None;
+            middleware_pong(ctx, &val);
+            None
+        }
+
+        LoguxMessage::Sync(val) => {
+            info!("Sync message received: {}", val.synced);
+            middleware_sync(act, ctx, &val);
+            // Acknowledge the sync with its own `added`, so the client
+            // marks every action up to it as delivered.
+            Some(LoguxMessage::Synced(SyncedMessage { synced: val.synced }))
+        }
+
+        LoguxMessage::Synced(val) => {
+            info!("Synced message received: {}", val.synced);
+            if let Some(client) = &act.client {
+                act.server.acknowledge(&client.node_id, val.synced);
+            }
+            None
+        }
+
+        LoguxMessage::Debug(val) => {
+            let node_id = act
+                .client
+                .as_ref()
+                .map_or("", |client| client.node_id.as_str());
+            match (val.debug_type.as_str(), &val.data) {
+                ("error", Value::String(text)) => {
+                    error!("Client {} error: {}", node_id, text);
+                    if let (Some(hook), Some(client)) =
+                        (&act.server.config.on_client_error, &act.client)
+                    {
+                        hook(client, text);
+                    }
+                }
+                (debug_type, data) => info!("Client {} debug {}: {}", node_id, debug_type, data),
+            }
+            None
+        }
+
+        LoguxMessage::Headers(val) => {
+            info!("Headers message received: {:?}", val.headers);
+            if let Some(client) = act.client.as_mut() {
+                client.headers = val.headers.clone();
+            }
+            act.headers = val.headers;
+            None
+        }
+    }
+}
+
+/// Lifecycle of a client connection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConnectionState {
+    /// Waiting for the `connect` message.
+    Connecting,
+    /// `connect` received, waiting for the authenticator answer.
+    Authenticating,
+    /// `connected` sent, every message is accepted.
+    Authenticated,
+    /// Connection closed by the server, messages are ignored.
+    Closed,
+}
+
+impl ConnectionState {
+    /// Check if a message kind can be handled in this state. Only `connect`,
+    /// `error` and `headers`, which clients send right after `connect`, can be
+    /// sent before authentication, and `connect` only once.
+    pub fn accepts(self, kind: MessageKind) -> bool {
+        match self {
+            ConnectionState::Connecting => matches!(
+                kind,
+                MessageKind::Connect | MessageKind::Error | MessageKind::Headers
+            ),
+            ConnectionState::Authenticating => {
+                matches!(kind, MessageKind::Error | MessageKind::Headers)
+            }
+            ConnectionState::Authenticated => kind != MessageKind::Connect,
+            ConnectionState::Closed => false,
+        }
+    }
+}
+
+/// Define http actor
+pub struct MyWs {
+    /// State shared with every other connection.
+    pub server: Arc<Server>,
+    /// Registry of live connections, to send actions to other clients.
+    pub broadcaster: Addr<Broadcaster>,
+    /// Id of this connection in the broadcaster and the subscriptions.
+    pub connection_id: ConnectionId,
+    /// Subprotocol version of the client, known after `connect`.
+    pub subprotocol: Option<Version>,
+    /// IP address of the client.
+    pub remote_address: Option<String>,
+    /// Client informations, known after a successful authentication.
+    pub client: Option<ClientInfo>,
+    pub state: ConnectionState,
+    /// Time of the last message from the client.
+    pub last_received: Instant,
+    /// Time of the ping waiting for a `pong`.
+    pub ping_sent: Option<Instant>,
+    /// How much the client clock is ahead of the server one, in milliseconds,
+    /// measured once on the first `sync` of the connection.
+    pub time_shift: Option<u64>,
+    /// Last headers sent by the client.
+    pub headers: Map<String, Value>,
+}
+
+impl MyWs {
+    /// Ping an authenticated client if nothing was received since the ping interval,
+    /// then close the connection if no `pong` comes back before the timeout.
+    fn ping(&mut self, ctx: &mut ws::WebsocketContext<MyWs>) {
+        let config = &self.server.config;
+        if self.state != ConnectionState::Authenticated
+            || self.ping_sent.is_some()
+            || self.last_received.elapsed() < config.ping_interval
+        {
+            return;
+        }
+
+        let sent = Instant::now();
+        self.ping_sent = Some(sent);
+        ctx.text(
+            LoguxMessage::Ping(PingMessage {
+                synced: self.server.store.lock().unwrap().last_added(),
+            })
+            .encode(),
+        );
+
+        let timeout = config.timeout;
+        ctx.run_later(timeout, move |act, ctx| {
+            if act.ping_sent == Some(sent) {
+                warn!("No pong received after {:?}", timeout);
+                ctx.text(
+                    LoguxMessage::Error(LoguxError::Timeout(timeout.as_millis() as u64)).encode(),
+                );
+                act.close(ctx);
+            }
+        });
+    }
+
+    /// Send log entries to the client in a `sync` message.
+    pub fn send_sync(&mut self, ctx: &mut ws::WebsocketContext<MyWs>, entries: Vec<LogEntry>) {
+        let synced = match entries.last() {
+            Some(entry) => entry.added,
+            None => return,
+        };
+        let actions = entries
+            .into_iter()
+            .map(|entry| (entry.action, entry.meta.for_client()))
+            .collect();
+        ctx.text(LoguxMessage::Sync(SyncMessage { synced, actions }).encode());
+    }
+
+    /// Resend the actions for the client added to the log after `synced`,
+    /// the last `added` it received in a previous connection, or after the last
+    /// one it acknowledged if it is greater, if they target its node, client or
+    /// user. A new connection has no subscriptions yet,
+    /// channel actions are resent when the client subscribes again.
+    fn resend(&mut self, ctx: &mut ws::WebsocketContext<MyWs>, synced: u64) {
+        let client = match &self.client {
+            Some(client) => client,
+            None => return,
+        };
+        let delivered = self.server.delivered(&client.node_id);
+        if delivered != synced {
+            debug!(
+                "Client {} reconnected with synced {}, {} was acknowledged",
+                client.node_id, synced, delivered
+            );
+        }
+
+        let entries: Vec<LogEntry> = self
+            .server
+            .store
+            .lock()
+            .unwrap()
+            .since(synced.max(delivered))
+            .into_iter()
+            .filter(|entry| client.is_receiver(&entry.meta, &HashSet::new()))
+            .collect();
+        if !entries.is_empty() {
+            info!("Resend {} actions to {}", entries.len(), client.node_id);
+            self.send_sync(ctx, entries);
+        }
+    }
+
+    /// Send an answer to a client action to every connection of this client
+    /// and to the other receivers of `meta`. It gets an `added` number but is
+    /// removed from the log right away, so a later connection never gets it.
+    pub fn send_server_action(&self, action: Action, mut meta: Meta) {
+        let client_id = match &self.client {
+            Some(client) => client.client_id.clone(),
+            None => return,
+        };
+        if !meta.clients.contains(&client_id) {
+            meta.clients.push(client_id);
+        }
+
+        let mut store = self.server.store.lock().unwrap();
+        if let Some(entry) = store.add(action, meta) {
+            store.remove(&entry.meta.id);
+            self.broadcast(vec![entry]);
+        }
+    }
+
+    /// Send actions just added to the log to every connection receiving them.
+    pub fn broadcast(&self, entries: Vec<LogEntry>) {
+        if !entries.is_empty() {
+            self.broadcaster.do_send(Broadcast { entries });
+        }
+    }
+
+    /// Tell the client its action was processed.
+    pub fn processed(&self, meta: &Meta) {
+        let mut payload = Map::new();
+        payload.insert(String::from("id"), Value::from(meta.id.as_str()));
+        self.send_server_action(Action::new("logux/processed", payload), self.server.meta());
+    }
+
+    /// Tell the client its action was refused and should be reverted. The
+    /// action may already be broadcast, so every receiver of it gets the undo
+    /// too, and it is removed from the log.
+    pub fn undo(&self, action: &Action, meta: &Meta, reason: UndoReason) {
+        self.server.store.lock().unwrap().remove(&meta.id);
+
+        let mut payload = Map::new();
+        payload.insert(String::from("id"), Value::from(meta.id.as_str()));
+        payload.insert(String::from("reason"), Value::from(reason.to_string()));
+        payload.insert(
+            String::from("action"),
+            serde_json::to_value(action).unwrap_or(Value::Null),
+        );
+        let mut undo_meta = self.server.meta();
+        undo_meta.channels = meta.channels.clone();
+        undo_meta.users = meta.users.clone();
+        undo_meta.clients = meta.clients.clone();
+        undo_meta.nodes = meta.nodes.clone();
+        self.send_server_action(Action::new("logux/undo", payload), undo_meta);
+    }
+
+    /// Send server headers to the client.
+    pub fn send_headers(
+        &mut self,
+        ctx: &mut ws::WebsocketContext<MyWs>,
+        headers: Map<String, Value>,
+    ) {
+        ctx.text(LoguxMessage::Headers(HeadersMessage { headers }).encode());
+    }
+
+    /// Send a server error to the client, only in development.
+    pub fn send_debug_error(&mut self, ctx: &mut ws::WebsocketContext<MyWs>, error: &str) {
+        if self.server.config.environment == Environment::Development {
+            ctx.text(
+                LoguxMessage::Debug(DebugMessage {
+                    debug_type: String::from("error"),
+                    data: Value::from(error),
+                })
+                .encode(),
+            );
+        }
+    }
+
+    /// Close the connection, every message received after is ignored.
+    pub fn close(&mut self, ctx: &mut ws::WebsocketContext<MyWs>) {
+        self.state = ConnectionState::Closed;
+        ctx.close(None);
+        ctx.stop();
+    }
+}
+
+impl Actor for MyWs {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let interval = self.server.config.ping_interval;
+        ctx.run_interval(interval, |act, ctx| act.ping(ctx));
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        self.server.unsubscribe_all(self.connection_id);
+        if self.client.is_some() {
+            self.broadcaster.do_send(Leave {
+                connection: self.connection_id,
+            });
+        }
+    }
+}
+
+impl Handler<SendSync> for MyWs {
+    type Result = ();
+
+    fn handle(&mut self, msg: SendSync, ctx: &mut Self::Context) {
+        self.send_sync(ctx, msg.entries);
+    }
+}
+
+/// Handler for ws::Message message
+impl StreamHandler<ws::Message, ws::ProtocolError> for MyWs {
+    // We should just provide this handle function with the same arguments so
+    // users can get their own actix_server running
+    fn handle(&mut self, msg: ws::Message, ctx: &mut Self::Context) {
+        match msg {
+            // Websocket ? To check if we delete it or not ?
+            ws::Message::Ping(msg) => {
+                info!("{}", &msg);
+                ctx.pong(&msg);
+            }
+            ws::Message::Text(text) => {
+                info!(" {}", &text);
+                self.last_received = Instant::now();
+                match LoguxMessage::decode(&text) {
+                    Ok(message) => {
+                        if self.state == ConnectionState::Closed {
+                            debug!("Connection closed, {} message ignored", message.kind());
+                        } else if message.kind() == MessageKind::Connect
+                            && !self.state.accepts(MessageKind::Connect)
+                        {
+                            warn!("connect message received twice");
+                            ctx.text(
+                                LoguxMessage::Error(LoguxError::WrongFormat(String::from(
+                                    "connect was already received",
+                                )))
+                                .encode(),
+                            );
+                        } else if !self.state.accepts(message.kind()) {
+                            warn!("{} message sent before authentication", message.kind());
+                            ctx.text(LoguxMessage::Error(LoguxError::MissedAuth(text)).encode());
+                            self.close(ctx);
+                        } else if let Some(answer) = process_action(self, message, ctx) {
+                            ctx.text(answer.encode());
+                        }
+                    }
+                    Err(e) => {
+                        error!("Message malformed: {}", e);
+                        ctx.text(LoguxMessage::Error(e).encode());
+                    }
+                }
+            }
+            _ => {
+                ctx.text(
+                    LoguxMessage::Error(LoguxError::WrongFormat(String::from("not an array")))
+                        .encode(),
+                );
+            }
+        }
+    }
+}
+
+/// Start a Logux connection from a websocket request.
+pub fn index(
+    req: HttpRequest,
+    stream: web::Payload,
+    server: web::Data<Arc<Server>>,
+    broadcaster: web::Data<Addr<Broadcaster>>,
+) -> std::result::Result<HttpResponse, Error> {
+    let resp = ws::start(
+        MyWs {
+            server: server.get_ref().clone(),
+            broadcaster: broadcaster.get_ref().clone(),
+            connection_id: server.connection_id(),
+            subprotocol: None,
+            remote_address: req.connection_info().remote().map(String::from),
+            client: None,
+            state: ConnectionState::Connecting,
+            last_received: Instant::now(),
+            ping_sent: None,
+            time_shift: None,
+            headers: Map::new(),
+        },
+        &req,
+        stream,
+    );
+    info!("{:?}", resp);
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [MessageKind; 9] = [
+        MessageKind::Error,
+        MessageKind::Connect,
+        MessageKind::Connected,
+        MessageKind::Ping,
+        MessageKind::Pong,
+        MessageKind::Sync,
+        MessageKind::Synced,
+        MessageKind::Debug,
+        MessageKind::Headers,
+    ];
+
+    #[test]
+    fn accepted_messages_by_state() {
+        use MessageKind::*;
+        let table: [(ConnectionState, &[MessageKind]); 4] = [
+            (ConnectionState::Connecting, &[Connect, Error, Headers]),
+            (ConnectionState::Authenticating, &[Error, Headers]),
+            (
+                ConnectionState::Authenticated,
+                &[Error, Connected, Ping, Pong, Sync, Synced, Debug, Headers],
+            ),
+            (ConnectionState::Closed, &[]),
+        ];
+        for (state, accepted) in table.iter() {
+            for kind in KINDS.iter() {
+                assert_eq!(
+                    state.accepts(*kind),
+                    accepted.contains(kind),
+                    "{:?} accepting {:?}",
+                    state,
+                    kind
+                );
+            }
+        }
+    }
+}
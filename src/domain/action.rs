@@ -0,0 +1,115 @@
+use crate::domain::action_id::ActionId;
+use crate::domain::messages::error::LoguxError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Logux action, a `type` with any other keys as payload.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Action {
+    /// Action type, like `user/rename`.
+    #[serde(rename = "type")]
+    pub action_type: String,
+    /// Every other key of the action.
+    #[serde(flatten)]
+    pub payload: Map<String, Value>,
+}
+
+/// Metadata of an action.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Meta {
+    /// Unique action id, `"<ms> <node-id> <seq>"`.
+    pub id: String,
+    /// Action creation time, in milliseconds since the epoch.
+    pub time: u64,
+    /// Position of the action in the log which stores it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added: Option<u64>,
+    /// Why the action is kept in the log, an action without reasons can be removed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+    /// Subprotocol version of the node which created the action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subprotocol: Option<String>,
+    /// Channels which should receive the action.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub channels: Vec<String>,
+    /// Node ids which should receive the action.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<String>,
+    /// Client ids which should receive the action.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clients: Vec<String>,
+    /// User ids which should receive the action.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+    /// Every other meta key.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Action {
+    pub fn new(action_type: &str, payload: Map<String, Value>) -> Action {
+        Action {
+            action_type: action_type.to_string(),
+            payload,
+        }
+    }
+}
+
+impl Meta {
+    /// Meta with only an id and a creation time.
+    pub fn new(id: String, time: u64) -> Meta {
+        Meta {
+            id,
+            time,
+            added: None,
+            reasons: Vec::new(),
+            subprotocol: None,
+            channels: Vec::new(),
+            nodes: Vec::new(),
+            clients: Vec::new(),
+            users: Vec::new(),
+            extra: Map::new(),
+        }
+    }
+
+    /// Parsed `id` of the meta.
+    pub fn action_id(&self) -> Result<ActionId, LoguxError> {
+        self.id.parse()
+    }
+
+    /// Meta exchanged with clients: only `id`, `time` and `subprotocol`, the
+    /// other keys are only meaningful for the server log.
+    pub fn for_client(&self) -> Meta {
+        Meta {
+            subprotocol: self.subprotocol.clone(),
+            ..Meta::new(self.id.clone(), self.time)
+        }
+    }
+
+    /// Merge `diff` keys into the meta. `id` and `added` identify the action
+    /// in a log and are never changed.
+    pub fn merge(&mut self, diff: Map<String, Value>) -> serde_json::Result<()> {
+        let mut value = serde_json::to_value(&*self)?;
+        if let Some(meta) = value.as_object_mut() {
+            for (key, value) in diff {
+                if key != "id" && key != "added" {
+                    meta.insert(key, value);
+                }
+            }
+        }
+        *self = serde_json::from_value(value)?;
+        Ok(())
+    }
+}
+
+/// Check an action and its meta beyond their JSON shape.
+pub fn validate_action(action: &Action, meta: &Meta) -> Result<(), LoguxError> {
+    if action.action_type.is_empty() {
+        return Err(LoguxError::WrongFormat(
+            "Invalid action: empty type".to_string(),
+        ));
+    }
+    meta.action_id()?;
+    Ok(())
+}
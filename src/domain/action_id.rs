@@ -0,0 +1,149 @@
+use crate::domain::messages::error::LoguxError;
+use crate::domain::time::now_ms;
+use std::fmt;
+use std::str::FromStr;
+
+/// Logux action id, `"<ms> <node-id> <seq>"`.
+///
+/// Ids are ordered like actions in a log: by creation time, then by node id,
+/// then by sequence number.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ActionId {
+    /// Creation time, in milliseconds since the epoch.
+    pub time: u64,
+    /// Node which created the action.
+    pub node_id: String,
+    /// Sequence number to distinguish actions created in the same millisecond.
+    pub seq: u64,
+}
+
+impl fmt::Display for ActionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.time, self.node_id, self.seq)
+    }
+}
+
+impl FromStr for ActionId {
+    type Err = LoguxError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        let invalid = || LoguxError::WrongFormat(format!("Invalid action id: {}", id));
+
+        let mut parts = id.split(' ');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(time), Some(node_id), Some(seq), None) if !node_id.is_empty() => Ok(ActionId {
+                time: time.parse().map_err(|_| invalid())?,
+                node_id: node_id.to_string(),
+                seq: seq.parse().map_err(|_| invalid())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Generate strictly increasing ids for the actions created by a node.
+pub struct ActionIdGenerator {
+    node_id: String,
+    last_time: u64,
+    seq: u64,
+}
+
+impl ActionIdGenerator {
+    pub fn new(node_id: &str) -> ActionIdGenerator {
+        ActionIdGenerator {
+            node_id: node_id.to_string(),
+            last_time: 0,
+            seq: 0,
+        }
+    }
+
+    /// Create a new id, greater than every id created before by this generator,
+    /// even if the system clock goes backward.
+    pub fn next_id(&mut self) -> ActionId {
+        let now = now_ms();
+
+        if now > self.last_time {
+            self.last_time = now;
+            self.seq = 0;
+        } else {
+            self.seq += 1;
+        }
+
+        ActionId {
+            time: self.last_time,
+            node_id: self.node_id.clone(),
+            seq: self.seq,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(time: u64, node_id: &str, seq: u64) -> ActionId {
+        ActionId {
+            time,
+            node_id: node_id.to_string(),
+            seq,
+        }
+    }
+
+    #[test]
+    fn reject_malformed_ids() {
+        for text in &[
+            "",
+            "1564508138460",
+            "1564508138460 server:x1",
+            "1564508138460 server:x1 0 1",
+            "1564508138460  0",
+            "now server:x1 0",
+            "-1 server:x1 0",
+            "1564508138460 server:x1 first",
+            "1564508138460 server:x1 0 ",
+        ] {
+            assert!(
+                text.parse::<ActionId>().is_err(),
+                "{:?} should be rejected",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn display_and_parse_round_trip() {
+        let action_id = id(1_564_508_138_460, "380:R7BNGAP5:px3-J3oc", 2);
+        let text = action_id.to_string();
+        assert_eq!(text, "1564508138460 380:R7BNGAP5:px3-J3oc 2");
+        assert_eq!(text.parse::<ActionId>().unwrap(), action_id);
+    }
+
+    #[test]
+    fn order_by_time_then_node_then_seq() {
+        assert!(id(1, "b", 5) < id(2, "a", 0));
+        assert!(id(1, "a", 5) < id(1, "b", 0));
+        assert!(id(1, "a", 0) < id(1, "a", 1));
+        assert!(id(10, "a", 0) > id(9, "a", 0));
+    }
+
+    #[test]
+    fn next_id_in_the_same_millisecond() {
+        let mut generator = ActionIdGenerator::new("server:x1");
+        // A clock going backward behaves like ids created in the same millisecond.
+        generator.last_time = u64::MAX;
+
+        let first = generator.next_id();
+        let second = generator.next_id();
+        assert_eq!(first.time, second.time);
+        assert_eq!(second.seq, first.seq + 1);
+        assert!(first < second);
+        assert_eq!(second.node_id, "server:x1");
+    }
+
+    #[test]
+    fn next_id_is_increasing() {
+        let mut generator = ActionIdGenerator::new("server:x1");
+        let ids: Vec<ActionId> = (0..100).map(|_| generator.next_id()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::domain::action::Meta;
+use crate::domain::node_id::parse_node_id;
+use semver::Version;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// What the server knows about a connected client.
+#[derive(Clone, Debug)]
+pub struct ClientInfo {
+    /// Node id sent in `connect`, `<user>:<client>:<tab>`.
+    pub node_id: String,
+    /// Client id, `<user>:<client>`, shared by every tab of a browser.
+    pub client_id: String,
+    /// User id, `None` if the node id has no user part.
+    pub user_id: Option<String>,
+    /// Subprotocol version of the client.
+    pub subprotocol: Version,
+    /// IP address of the client.
+    pub remote_address: Option<String>,
+    /// Last headers sent by the client, like its language or application version.
+    pub headers: Map<String, Value>,
+}
+
+impl ClientInfo {
+    pub fn new(
+        node_id: &str,
+        subprotocol: Version,
+        remote_address: Option<String>,
+        headers: Map<String, Value>,
+    ) -> ClientInfo {
+        let (user_id, client_id) = parse_node_id(node_id);
+        ClientInfo {
+            node_id: node_id.to_string(),
+            client_id,
+            user_id,
+            subprotocol,
+            remote_address,
+            headers,
+        }
+    }
+
+    /// Check if an action should be sent to this client, from its meta `nodes`,
+    /// `clients`, `users` and `channels`. Actions created by the client itself are
+    /// never sent back.
+    pub fn is_receiver(&self, meta: &Meta, channels: &HashSet<String>) -> bool {
+        let own = meta.action_id().is_ok_and(|id| id.node_id == self.node_id);
+        !own && (meta.nodes.contains(&self.node_id)
+            || meta.clients.contains(&self.client_id)
+            || self
+                .user_id
+                .as_ref()
+                .is_some_and(|user_id| meta.users.contains(user_id))
+            || meta
+                .channels
+                .iter()
+                .any(|channel| channels.contains(channel)))
+    }
+}
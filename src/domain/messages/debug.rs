@@ -0,0 +1,8 @@
+use serde_json::Value;
+
+pub struct DebugMessage {
+    /// Kind of debug data, like `error`.
+    pub debug_type: String,
+    /// Debug data, an error stack trace for `error`.
+    pub data: Value,
+}
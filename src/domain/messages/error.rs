@@ -1,7 +1,8 @@
-use super::messagesKind::MessageKind;
+use serde_json::{json, Value};
 use std::fmt;
 
-enum ErrorMessageKind {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorMessageKind {
     /// Client Logux protocol version is not supported by server.
     WrongProtocol,
     /// Message is not correct JSON, is not an array or have no kind.
@@ -18,6 +19,22 @@ enum ErrorMessageKind {
     WrongSubprotocol,
 }
 
+impl ErrorMessageKind {
+    /// Error kind from the second element of an error message, `None` if unknown.
+    pub fn parse(kind: &str) -> Option<ErrorMessageKind> {
+        match kind {
+            "wrong-credentials" => Some(ErrorMessageKind::WrongCredentials),
+            "wrong-format" => Some(ErrorMessageKind::WrongFormat),
+            "wrong-protocol" => Some(ErrorMessageKind::WrongProtocol),
+            "wrong-subprotocol" => Some(ErrorMessageKind::WrongSubprotocol),
+            "unknown-message" => Some(ErrorMessageKind::UnkownMessage),
+            "missed-auth" => Some(ErrorMessageKind::MissedAuth),
+            "timeout" => Some(ErrorMessageKind::Timeout),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for ErrorMessageKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -32,90 +49,125 @@ impl fmt::Display for ErrorMessageKind {
     }
 }
 
-pub struct WrongProtocolErrorMessage {
-    /// Key with minimum supported version.
-    supported: String,
-    /// Key with the used version.
-    used: String,
-}
-
-impl fmt::Display for WrongProtocolErrorMessage {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "[\"{}\", \"{}\", \"{}\", \"{}\"]",
-            &MessageKind::Error,
-            &ErrorMessageKind::WrongProtocol,
-            &self.supported,
-            &self.used,
-        )
-    }
-}
-
-pub struct WrongFormatErrorMessage {
+/// Error of the Logux protocol, sent as `["error", kind, options]`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoguxError {
+    WrongProtocol {
+        /// Minimum supported version.
+        supported: u64,
+        /// Used version.
+        used: u64,
+    },
     /// Bad message string.
-    pub message: String,
+    WrongFormat(String),
+    /// Message with an unknown type.
+    UnknownMessage(String),
+    WrongCredentials,
+    /// Message sent before authentication.
+    MissedAuth(String),
+    /// Reached timeout, in milliseconds.
+    Timeout(u64),
+    WrongSubprotocol {
+        /// Supported versions range.
+        supported: String,
+        /// Used version.
+        used: String,
+    },
 }
 
-impl fmt::Display for WrongFormatErrorMessage {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "[\"{}\", \"{}\", \"{}\"]",
-            &MessageKind::Error,
-            &ErrorMessageKind::WrongFormat,
-            &self.message
-        )
+impl LoguxError {
+    pub fn kind(&self) -> ErrorMessageKind {
+        match self {
+            LoguxError::WrongProtocol { .. } => ErrorMessageKind::WrongProtocol,
+            LoguxError::WrongFormat(_) => ErrorMessageKind::WrongFormat,
+            LoguxError::UnknownMessage(_) => ErrorMessageKind::UnkownMessage,
+            LoguxError::WrongCredentials => ErrorMessageKind::WrongCredentials,
+            LoguxError::MissedAuth(_) => ErrorMessageKind::MissedAuth,
+            LoguxError::Timeout(_) => ErrorMessageKind::Timeout,
+            LoguxError::WrongSubprotocol { .. } => ErrorMessageKind::WrongSubprotocol,
+        }
     }
-}
 
-/*
-impl Serialize for WrongFormatErrorMessage {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut state = serializer.serialize_struct("WrongProtocolErrorMessage", 1)?;
-        state.serialize_field("type", &MessageKind::Error)?;
-        state.serialize_field("errorType", &ErrorMessageKind::WrongSubprotocol)?;
-        state.serialize_field("message", &self.message)?;
-        state.end()
+    /// Options sent after the error kind, `None` for errors without options.
+    pub fn options(&self) -> Option<Value> {
+        match self {
+            LoguxError::WrongProtocol { supported, used } => {
+                Some(json!({ "supported": supported, "used": used }))
+            }
+            LoguxError::WrongFormat(message)
+            | LoguxError::UnknownMessage(message)
+            | LoguxError::MissedAuth(message) => Some(Value::from(message.as_str())),
+            LoguxError::WrongCredentials => None,
+            LoguxError::Timeout(timeout) => Some(Value::from(*timeout)),
+            LoguxError::WrongSubprotocol { supported, used } => {
+                Some(json!({ "supported": supported, "used": used }))
+            }
+        }
     }
-}
-*/
 
-pub struct UnkownMessageErrorMessage {
-    /// Bad message type string.
-    pub message_type: String,
-}
+    /// Build an error from its kind and options, as sent by the other node.
+    pub fn from_options(kind: &str, options: Option<Value>) -> Result<LoguxError, String> {
+        let kind = ErrorMessageKind::parse(kind).ok_or(format!("unknown error {}", kind))?;
+        let invalid = || format!("Invalid {} error options", kind);
+        let string = |value: Option<Value>| match value {
+            Some(Value::String(s)) => Ok(s),
+            _ => Err(invalid()),
+        };
 
-impl fmt::Display for UnkownMessageErrorMessage {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "[\"{}\", \"{}\", \"{}\"]",
-            &MessageKind::Error,
-            &ErrorMessageKind::UnkownMessage,
-            &self.message_type,
-        )
+        match kind {
+            ErrorMessageKind::WrongProtocol => {
+                let options = options.ok_or_else(invalid)?;
+                match (options["supported"].as_u64(), options["used"].as_u64()) {
+                    (Some(supported), Some(used)) => {
+                        Ok(LoguxError::WrongProtocol { supported, used })
+                    }
+                    _ => Err(invalid()),
+                }
+            }
+            ErrorMessageKind::WrongFormat => Ok(LoguxError::WrongFormat(string(options)?)),
+            ErrorMessageKind::UnkownMessage => Ok(LoguxError::UnknownMessage(string(options)?)),
+            ErrorMessageKind::WrongCredentials => Ok(LoguxError::WrongCredentials),
+            ErrorMessageKind::MissedAuth => Ok(LoguxError::MissedAuth(string(options)?)),
+            ErrorMessageKind::Timeout => options
+                .and_then(|timeout| timeout.as_u64())
+                .map(LoguxError::Timeout)
+                .ok_or_else(invalid),
+            ErrorMessageKind::WrongSubprotocol => {
+                let options = options.ok_or_else(invalid)?;
+                match (options["supported"].as_str(), options["used"].as_str()) {
+                    (Some(supported), Some(used)) => Ok(LoguxError::WrongSubprotocol {
+                        supported: supported.to_string(),
+                        used: used.to_string(),
+                    }),
+                    _ => Err(invalid()),
+                }
+            }
+        }
     }
 }
 
-struct MissedAuthErrorMessage {
-    r#type: MessageKind,
-    /// Bad message string.
-    message: String,
-}
-
-struct TimeoutErrorMessage {
-    r#type: MessageKind,
-    timeout_duration: String,
+impl fmt::Display for LoguxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoguxError::WrongProtocol { supported, used } => write!(
+                f,
+                "Logux protocol {} is not supported, {} or newer is required",
+                used, supported
+            ),
+            LoguxError::WrongFormat(message) => write!(f, "Wrong message format: {}", message),
+            LoguxError::UnknownMessage(message) => write!(f, "Unknown message: {}", message),
+            LoguxError::WrongCredentials => write!(f, "Wrong credentials"),
+            LoguxError::MissedAuth(message) => {
+                write!(f, "Message sent before authentication: {}", message)
+            }
+            LoguxError::Timeout(timeout) => write!(f, "Timeout of {} ms reached", timeout),
+            LoguxError::WrongSubprotocol { supported, used } => write!(
+                f,
+                "Subprotocol {} is not supported, {} is required",
+                used, supported
+            ),
+        }
+    }
 }
 
-struct WrongSubProtocolErrorMessage {
-    r#type: MessageKind,
-    /// Key with minimum supported version.
-    supported: String,
-    /// Key with the used version.
-    used: String,
-}
+impl std::error::Error for LoguxError {}
@@ -0,0 +1,6 @@
+use serde_json::{Map, Value};
+
+pub struct HeadersMessage {
+    /// Headers of the sender, like its language or application version.
+    pub headers: Map<String, Value>,
+}
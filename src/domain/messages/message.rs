@@ -0,0 +1,394 @@
+use crate::domain::action::{validate_action, Action, Meta};
+use crate::domain::messages::connect::{ConnectMessage, OptionnalConnectMessage};
+use crate::domain::messages::connected::{ConnectedMessage, OptionnalConnectedMessage};
+use crate::domain::messages::debug::DebugMessage;
+use crate::domain::messages::error::LoguxError;
+use crate::domain::messages::headers::HeadersMessage;
+use crate::domain::messages::lib::LoguxEvent;
+use crate::domain::messages::messagesKind::MessageKind;
+use crate::domain::messages::ping::PingMessage;
+use crate::domain::messages::pong::PongMessage;
+use crate::domain::messages::sync::SyncMessage;
+use crate::domain::messages::synced::SyncedMessage;
+use serde::de::{self, IgnoredAny, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Any message of the Logux protocol.
+pub enum LoguxMessage {
+    Error(LoguxError),
+    Connect(ConnectMessage),
+    Connected(ConnectedMessage),
+    Ping(PingMessage),
+    Pong(PongMessage),
+    Sync(SyncMessage),
+    Synced(SyncedMessage),
+    Debug(DebugMessage),
+    Headers(HeadersMessage),
+}
+
+impl LoguxMessage {
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            LoguxMessage::Error(_) => MessageKind::Error,
+            LoguxMessage::Connect(_) => MessageKind::Connect,
+            LoguxMessage::Connected(_) => MessageKind::Connected,
+            LoguxMessage::Ping(_) => MessageKind::Ping,
+            LoguxMessage::Pong(_) => MessageKind::Pong,
+            LoguxMessage::Sync(_) => MessageKind::Sync,
+            LoguxMessage::Synced(_) => MessageKind::Synced,
+            LoguxMessage::Debug(_) => MessageKind::Debug,
+            LoguxMessage::Headers(_) => MessageKind::Headers,
+        }
+    }
+
+    /// Function to decode a JSON message.
+    pub fn decode(text: &str) -> Result<LoguxMessage, LoguxError> {
+        let value: Value = serde_json::from_str(text).map_err(|_| {
+            LoguxError::WrongFormat(String::from(
+                "incorrect format, please refer to: https://github.com/logux/logux/blob/master/protocol/spec.md",
+            ))
+        })?;
+
+        match value.as_array().map(|vec| vec.first()) {
+            Some(Some(Value::String(kind))) => {
+                if MessageKind::parse(kind).is_none() {
+                    return Err(LoguxError::UnknownMessage(text.to_string()));
+                }
+            }
+            Some(None) => return Err(LoguxError::WrongFormat(String::from("array is empty"))),
+            _ => {
+                return Err(LoguxError::WrongFormat(String::from(
+                    "not an array with a type",
+                )))
+            }
+        }
+
+        LoguxMessage::deserialize(&value).map_err(|e| LoguxError::WrongFormat(e.to_string()))
+    }
+}
+
+impl LoguxEvent for LoguxMessage {
+    fn encode(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+impl Serialize for LoguxMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(None)?;
+        seq.serialize_element(&self.kind().to_string())?;
+        match self {
+            LoguxMessage::Error(error) => {
+                seq.serialize_element(&error.kind().to_string())?;
+                if let Some(options) = error.options() {
+                    seq.serialize_element(&options)?;
+                }
+            }
+            LoguxMessage::Connect(msg) => {
+                seq.serialize_element(&msg.protocol)?;
+                seq.serialize_element(&msg.node_id)?;
+                seq.serialize_element(&msg.synced)?;
+                if let Some(options) = &msg.options {
+                    seq.serialize_element(options)?;
+                }
+            }
+            LoguxMessage::Connected(msg) => {
+                seq.serialize_element(&msg.protocol)?;
+                seq.serialize_element(&msg.node_id)?;
+                seq.serialize_element(&msg.time_sync)?;
+                if let Some(options) = &msg.options {
+                    seq.serialize_element(options)?;
+                }
+            }
+            LoguxMessage::Ping(msg) => seq.serialize_element(&msg.synced)?,
+            LoguxMessage::Pong(msg) => seq.serialize_element(&msg.synced)?,
+            LoguxMessage::Sync(msg) => {
+                seq.serialize_element(&msg.synced)?;
+                for (action, meta) in &msg.actions {
+                    seq.serialize_element(action)?;
+                    seq.serialize_element(meta)?;
+                }
+            }
+            LoguxMessage::Synced(msg) => seq.serialize_element(&msg.synced)?,
+            LoguxMessage::Debug(msg) => {
+                seq.serialize_element(&msg.debug_type)?;
+                seq.serialize_element(&msg.data)?;
+            }
+            LoguxMessage::Headers(msg) => seq.serialize_element(&msg.headers)?,
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for LoguxMessage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(LoguxMessageVisitor)
+    }
+}
+
+struct LoguxMessageVisitor;
+
+/// Next required element of a message.
+fn required<'de, A, T>(seq: &mut A, kind: MessageKind) -> Result<T, A::Error>
+where
+    A: SeqAccess<'de>,
+    T: Deserialize<'de>,
+{
+    seq.next_element()?.ok_or_else(|| {
+        de::Error::custom(format!(
+            "Invalid {} type, please refer to the documentation.",
+            kind
+        ))
+    })
+}
+
+impl<'de> Visitor<'de> for LoguxMessageVisitor {
+    type Value = LoguxMessage;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a Logux message array")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<LoguxMessage, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let kind: String = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::custom("array is empty"))?;
+        let kind = MessageKind::parse(&kind)
+            .ok_or_else(|| de::Error::custom(format!("unknown message type {}", kind)))?;
+
+        let message = match kind {
+            MessageKind::Error => {
+                let error_kind: String = required(&mut seq, kind)?;
+                let options = seq.next_element::<Value>()?;
+                LoguxMessage::Error(
+                    LoguxError::from_options(&error_kind, options).map_err(de::Error::custom)?,
+                )
+            }
+            MessageKind::Connect => LoguxMessage::Connect(ConnectMessage {
+                protocol: required(&mut seq, kind)?,
+                node_id: required(&mut seq, kind)?,
+                synced: required(&mut seq, kind)?,
+                options: seq
+                    .next_element::<Option<OptionnalConnectMessage>>()?
+                    .and_then(|options| options),
+            }),
+            MessageKind::Connected => LoguxMessage::Connected(ConnectedMessage {
+                protocol: required(&mut seq, kind)?,
+                node_id: required(&mut seq, kind)?,
+                time_sync: required(&mut seq, kind)?,
+                options: seq
+                    .next_element::<Option<OptionnalConnectedMessage>>()?
+                    .and_then(|options| options),
+            }),
+            MessageKind::Ping => LoguxMessage::Ping(PingMessage {
+                synced: required(&mut seq, kind)?,
+            }),
+            MessageKind::Pong => LoguxMessage::Pong(PongMessage {
+                synced: required(&mut seq, kind)?,
+            }),
+            MessageKind::Sync => {
+                let synced = required(&mut seq, kind)?;
+                let mut actions = Vec::new();
+                while let Some(action) = seq.next_element::<Action>()? {
+                    let meta: Meta = seq.next_element()?.ok_or_else(|| {
+                        de::Error::custom("Invalid sync type, every action should have a meta.")
+                    })?;
+                    validate_action(&action, &meta).map_err(de::Error::custom)?;
+                    actions.push((action, meta));
+                }
+                LoguxMessage::Sync(SyncMessage { synced, actions })
+            }
+            MessageKind::Synced => LoguxMessage::Synced(SyncedMessage {
+                synced: required(&mut seq, kind)?,
+            }),
+            MessageKind::Debug => LoguxMessage::Debug(DebugMessage {
+                debug_type: required(&mut seq, kind)?,
+                data: required(&mut seq, kind)?,
+            }),
+            MessageKind::Headers => LoguxMessage::Headers(HeadersMessage {
+                headers: required::<_, Map<String, Value>>(&mut seq, kind)?,
+            }),
+        };
+
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::custom(format!(
+                "Invalid {} type, too many elements.",
+                kind
+            )));
+        }
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Decode an encoded message and check nothing was lost.
+    fn round_trip(message: LoguxMessage) {
+        let text = message.encode();
+        let decoded = LoguxMessage::decode(&text).unwrap();
+        assert_eq!(decoded.kind(), message.kind());
+        assert_eq!(decoded.encode(), text);
+    }
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn round_trip_errors() {
+        round_trip(LoguxMessage::Error(LoguxError::WrongProtocol {
+            supported: 3,
+            used: 2,
+        }));
+        round_trip(LoguxMessage::Error(LoguxError::WrongFormat(String::from(
+            "[\"ping\"",
+        ))));
+        round_trip(LoguxMessage::Error(LoguxError::UnknownMessage(
+            String::from("[\"hello\"]"),
+        )));
+        round_trip(LoguxMessage::Error(LoguxError::WrongCredentials));
+        round_trip(LoguxMessage::Error(LoguxError::MissedAuth(String::from(
+            "[\"ping\",1]",
+        ))));
+        round_trip(LoguxMessage::Error(LoguxError::Timeout(70000)));
+        round_trip(LoguxMessage::Error(LoguxError::WrongSubprotocol {
+            supported: String::from(">= 1.0.0"),
+            used: String::from("0.1.0"),
+        }));
+    }
+
+    #[test]
+    fn round_trip_connect() {
+        round_trip(LoguxMessage::Connect(ConnectMessage {
+            protocol: 4,
+            node_id: String::from("10:uImkcF4z"),
+            synced: 0,
+            options: None,
+        }));
+        round_trip(LoguxMessage::Connect(ConnectMessage {
+            protocol: 4,
+            node_id: String::from("10:uImkcF4z"),
+            synced: 12,
+            options: Some(OptionnalConnectMessage {
+                subprotocol: Some(String::from("1.0.0")),
+                credentials: Some(Value::from("token")),
+            }),
+        }));
+    }
+
+    #[test]
+    fn round_trip_connected() {
+        round_trip(LoguxMessage::Connected(ConnectedMessage {
+            protocol: 4,
+            node_id: String::from("server:x1"),
+            time_sync: [1_475_316_481_050, 1_475_316_481_051],
+            options: None,
+        }));
+        round_trip(LoguxMessage::Connected(ConnectedMessage {
+            protocol: 4,
+            node_id: String::from("server:x1"),
+            time_sync: [1_475_316_481_050, 1_475_316_481_051],
+            options: Some(OptionnalConnectedMessage {
+                subprotocol: Some(String::from("1.0.0")),
+                credentials: None,
+                extra: object(json!({ "env": "development" })),
+            }),
+        }));
+    }
+
+    #[test]
+    fn round_trip_other_messages() {
+        round_trip(LoguxMessage::Ping(PingMessage { synced: 1 }));
+        round_trip(LoguxMessage::Pong(PongMessage { synced: 2 }));
+        round_trip(LoguxMessage::Synced(SyncedMessage { synced: 3 }));
+        round_trip(LoguxMessage::Debug(DebugMessage {
+            debug_type: String::from("error"),
+            data: Value::from("Error: Wrong action"),
+        }));
+        round_trip(LoguxMessage::Headers(HeadersMessage {
+            headers: object(json!({ "lang": "fr" })),
+        }));
+    }
+
+    #[test]
+    fn round_trip_sync() {
+        let mut meta = Meta::new(String::from("1564508138460 380:R7BNGAP5:px3-J3oc 0"), 1);
+        meta.subprotocol = Some(String::from("1.0.0"));
+        round_trip(LoguxMessage::Sync(SyncMessage {
+            synced: 1,
+            actions: vec![
+                (
+                    Action::new("user/rename", object(json!({ "name": "Ada" }))),
+                    meta.clone(),
+                ),
+                (
+                    Action::new("user/remove", Map::new()),
+                    Meta::new(String::from("1564508138461 380:R7BNGAP5:px3-J3oc 0"), 2),
+                ),
+            ],
+        }));
+    }
+
+    #[test]
+    fn reject_too_many_elements() {
+        for text in &[
+            r#"["ping",1,2]"#,
+            r#"["synced",1,2]"#,
+            r#"["error","timeout",70000,1]"#,
+            r#"["connect",4,"10:uImkcF4z",0,{},1]"#,
+            r#"["headers",{},{}]"#,
+        ] {
+            match LoguxMessage::decode(text) {
+                Err(LoguxError::WrongFormat(message)) => {
+                    assert!(message.contains("too many elements"), "{}", message)
+                }
+                _ => panic!("{} should be rejected", text),
+            }
+        }
+    }
+
+    #[test]
+    fn reject_sync_without_meta() {
+        let text = r#"["sync",1,{"type":"user/rename"}]"#;
+        match LoguxMessage::decode(text) {
+            Err(LoguxError::WrongFormat(message)) => {
+                assert!(message.contains("should have a meta"), "{}", message)
+            }
+            _ => panic!("{} should be rejected", text),
+        }
+    }
+
+    #[test]
+    fn reject_unknown_error_kind() {
+        let text = r#"["error","wrong-mood"]"#;
+        match LoguxMessage::decode(text) {
+            Err(LoguxError::WrongFormat(message)) => {
+                assert!(message.contains("unknown error"), "{}", message)
+            }
+            _ => panic!("{} should be rejected", text),
+        }
+    }
+
+    #[test]
+    fn reject_unknown_message() {
+        assert_eq!(
+            LoguxMessage::decode(r#"["hello",1]"#).err(),
+            Some(LoguxError::UnknownMessage(String::from(r#"["hello",1]"#)))
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MessageKind {
     Error,
     Connect,
@@ -9,6 +10,25 @@ pub enum MessageKind {
     Sync,
     Synced,
     Debug,
+    Headers,
+}
+
+impl MessageKind {
+    /// Message kind from the first element of a message, `None` if unknown.
+    pub fn parse(kind: &str) -> Option<MessageKind> {
+        match kind {
+            "error" => Some(MessageKind::Error),
+            "connect" => Some(MessageKind::Connect),
+            "connected" => Some(MessageKind::Connected),
+            "ping" => Some(MessageKind::Ping),
+            "pong" => Some(MessageKind::Pong),
+            "sync" => Some(MessageKind::Sync),
+            "synced" => Some(MessageKind::Synced),
+            "debug" => Some(MessageKind::Debug),
+            "headers" => Some(MessageKind::Headers),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for MessageKind {
@@ -22,7 +42,7 @@ impl fmt::Display for MessageKind {
             MessageKind::Sync => write!(f, "sync"),
             MessageKind::Synced => write!(f, "synced"),
             MessageKind::Debug => write!(f, "debug"),
+            MessageKind::Headers => write!(f, "headers"),
         }
     }
 }
-
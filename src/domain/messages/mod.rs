@@ -1,7 +1,11 @@
 pub mod connect;
 pub mod connected;
+pub mod debug;
 pub mod error;
+pub mod headers;
 pub mod lib;
+pub mod message;
+#[allow(non_snake_case)]
 pub mod messagesKind;
 pub mod ping;
 pub mod pong;
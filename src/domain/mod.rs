@@ -1 +1,7 @@
+pub mod action;
+pub mod action_id;
+pub mod client;
 pub mod messages;
+pub mod node_id;
+pub mod store;
+pub mod time;
@@ -0,0 +1,29 @@
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+
+/// Prefix of generated server node ids.
+pub const SERVER_NODE_PREFIX: &str = "server";
+
+/// Length of the random part of a generated node id.
+const RANDOM_LENGTH: usize = 8;
+
+/// Generate a node id, `<prefix>:<random>`, unique across the network.
+pub fn generate_node_id(prefix: Option<&str>) -> String {
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(RANDOM_LENGTH)
+        .collect();
+    format!("{}:{}", prefix.unwrap_or(SERVER_NODE_PREFIX), random)
+}
+
+/// Split a client node id, `<user>:<client>:<tab>`, into its user id and client id.
+/// A node id without `:` has no user and is its own client id.
+pub fn parse_node_id(node_id: &str) -> (Option<String>, String) {
+    let mut parts = node_id.split(':');
+    match (parts.next(), parts.next()) {
+        (Some(user_id), Some(client)) => {
+            (Some(user_id.to_string()), format!("{}:{}", user_id, client))
+        }
+        _ => (None, node_id.to_string()),
+    }
+}
@@ -0,0 +1,42 @@
+use crate::domain::action::{Action, Meta};
+use serde_json::{Map, Value};
+use std::sync::{Arc, Mutex};
+
+/// Store shared between every connection of the server.
+pub type SharedStore = Arc<Mutex<dyn Store + Send>>;
+
+/// An action with its meta, as kept in the server log.
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    /// Sequence number given by the store when the action was added.
+    /// It is strictly increasing across the whole log.
+    pub added: u64,
+    pub action: Action,
+    pub meta: Meta,
+}
+
+/// Storage backend for the server action log.
+pub trait Store {
+    /// Add an action to the log and give it the next `added` number.
+    /// Returns `None` if the action is already in the log.
+    fn add(&mut self, action: Action, meta: Meta) -> Option<LogEntry>;
+
+    /// Check if an action with this id is in the log.
+    fn has(&self, id: &str) -> bool;
+
+    /// Get an action by its meta id.
+    fn get(&self, id: &str) -> Option<LogEntry>;
+
+    /// Every entry added after `added`, in log order.
+    fn since(&self, added: u64) -> Vec<LogEntry>;
+
+    /// Remove an action from the log, returning it if it was there.
+    fn remove(&mut self, id: &str) -> Option<LogEntry>;
+
+    /// Merge `diff` keys into the meta of an action.
+    /// Returns `false` if the action is not in the log or the diff is not a valid meta.
+    fn change_meta(&mut self, id: &str, diff: Map<String, Value>) -> bool;
+
+    /// Highest `added` number given by the store, 0 on an empty log.
+    fn last_added(&self) -> u64;
+}
@@ -0,0 +1,62 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current time, in milliseconds since the epoch.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// Estimate how much a client clock is ahead of the server one, from the
+/// creation time of an action and the time the server received it.
+///
+/// An action can't be created after it was received, so a creation time in
+/// the future can only come from a skewed client clock. A creation time in
+/// the past may come from an action created offline, so it tells nothing.
+pub fn time_shift(created: u64, received: u64) -> u64 {
+    created.saturating_sub(received)
+}
+
+/// Client `time` in the server clock.
+pub fn server_time(time: u64, shift: u64) -> u64 {
+    time.saturating_sub(shift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_shift_of_a_client_ahead() {
+        assert_eq!(time_shift(1_000_500, 1_000_000), 500);
+    }
+
+    #[test]
+    fn time_shift_ignores_past_actions() {
+        assert_eq!(time_shift(1_000_000, 1_000_000), 0);
+        // Queued while offline, an hour before the reconnection.
+        assert_eq!(time_shift(1_000_000, 1_000_000 + 3_600_000), 0);
+    }
+
+    #[test]
+    fn server_time_removes_the_shift() {
+        assert_eq!(server_time(1_000_500, 500), 1_000_000);
+        assert_eq!(server_time(1_000_000, 0), 1_000_000);
+        assert_eq!(server_time(100, 500), 0);
+    }
+
+    #[test]
+    fn reconnect_with_old_actions() {
+        let received = 10_000_000;
+        let queued = [received - 3_600_000, received - 60_000];
+        let shift = queued
+            .iter()
+            .map(|&created| time_shift(created, received))
+            .max()
+            .unwrap();
+        assert_eq!(shift, 0);
+        assert_eq!(server_time(queued[0], shift), queued[0]);
+        assert_eq!(server_time(queued[1], shift), queued[1]);
+    }
+}
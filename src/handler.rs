@@ -0,0 +1,66 @@
+use crate::domain::action::{Action, Meta};
+use crate::domain::client::ClientInfo;
+use futures::{future, Future};
+use std::fmt;
+
+/// Processing result, resolved possibly later.
+pub type ProcessFuture = Box<dyn Future<Item = (), Error = String>>;
+
+/// Why a client action was undone, sent in `logux/undo`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UndoReason {
+    /// The handler `access` refused the action.
+    Denied,
+    /// No handler for the action type.
+    UnknownType,
+    /// The handler `process` failed.
+    Error,
+    /// The subscribed channel doesn't exist.
+    WrongChannel,
+}
+
+impl fmt::Display for UndoReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UndoReason::Denied => write!(f, "denied"),
+            UndoReason::UnknownType => write!(f, "unknownType"),
+            UndoReason::Error => write!(f, "error"),
+            UndoReason::WrongChannel => write!(f, "wrongChannel"),
+        }
+    }
+}
+
+/// Receivers of an action, added to its meta before it is stored.
+#[derive(Clone, Debug, Default)]
+pub struct Resend {
+    pub channels: Vec<String>,
+    pub users: Vec<String>,
+    pub clients: Vec<String>,
+    pub nodes: Vec<String>,
+}
+
+impl Resend {
+    /// Add the receivers to an action meta.
+    pub fn apply(self, meta: &mut Meta) {
+        meta.channels.extend(self.channels);
+        meta.users.extend(self.users);
+        meta.clients.extend(self.clients);
+        meta.nodes.extend(self.nodes);
+    }
+}
+
+/// Callbacks for an action type sent by clients.
+pub trait ActionHandler: Send + Sync {
+    /// Check if the client can send this action.
+    fn access(&self, client: &ClientInfo, action: &Action, meta: &Meta) -> bool;
+
+    /// Apply the action, after it was added to the log.
+    fn process(&self, _client: &ClientInfo, _action: &Action, _meta: &Meta) -> ProcessFuture {
+        Box::new(future::ok(()))
+    }
+
+    /// Decide who else should receive the action.
+    fn resend(&self, _client: &ClientInfo, _action: &Action, _meta: &Meta) -> Option<Resend> {
+        None
+    }
+}
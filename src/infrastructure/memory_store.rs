@@ -0,0 +1,155 @@
+use crate::domain::action::{Action, Meta};
+use crate::domain::store::{LogEntry, Store};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Action log kept in memory, lost when the server stops.
+#[derive(Default)]
+pub struct MemoryStore {
+    last_added: u64,
+    /// Entries indexed by their `added` number, so iteration follows log order.
+    entries: BTreeMap<u64, LogEntry>,
+    /// Meta id to `added` number.
+    ids: HashMap<String, u64>,
+}
+
+impl Store for MemoryStore {
+    fn add(&mut self, action: Action, mut meta: Meta) -> Option<LogEntry> {
+        if self.ids.contains_key(&meta.id) {
+            return None;
+        }
+
+        self.last_added += 1;
+        let added = self.last_added;
+        meta.added = Some(added);
+
+        let entry = LogEntry {
+            added,
+            action,
+            meta,
+        };
+        self.ids.insert(entry.meta.id.clone(), added);
+        self.entries.insert(added, entry.clone());
+        Some(entry)
+    }
+
+    fn has(&self, id: &str) -> bool {
+        self.ids.contains_key(id)
+    }
+
+    fn get(&self, id: &str) -> Option<LogEntry> {
+        self.ids
+            .get(id)
+            .and_then(|added| self.entries.get(added))
+            .cloned()
+    }
+
+    fn since(&self, added: u64) -> Vec<LogEntry> {
+        self.entries
+            .range(added + 1..)
+            .map(|(_, entry)| entry.clone())
+            .collect()
+    }
+
+    fn remove(&mut self, id: &str) -> Option<LogEntry> {
+        let added = self.ids.remove(id)?;
+        self.entries.remove(&added)
+    }
+
+    fn change_meta(&mut self, id: &str, diff: Map<String, Value>) -> bool {
+        let entries = &mut self.entries;
+        let entry = match self.ids.get(id).and_then(|added| entries.get_mut(added)) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        entry.meta.merge(diff).is_ok()
+    }
+
+    fn last_added(&self) -> u64 {
+        self.last_added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn add(store: &mut MemoryStore, id: &str) -> Option<LogEntry> {
+        store.add(
+            Action::new("user/rename", Map::new()),
+            Meta::new(id.to_string(), 1),
+        )
+    }
+
+    fn ids(entries: Vec<LogEntry>) -> Vec<String> {
+        entries.into_iter().map(|entry| entry.meta.id).collect()
+    }
+
+    #[test]
+    fn added_is_increasing() {
+        let mut store = MemoryStore::default();
+        assert_eq!(store.last_added(), 0);
+        let first = add(&mut store, "1 a 0").unwrap();
+        let second = add(&mut store, "1 a 1").unwrap();
+        assert_eq!(first.added, 1);
+        assert_eq!(second.added, 2);
+        assert_eq!(second.meta.added, Some(2));
+
+        // Numbers of removed entries are never given again.
+        store.remove("1 a 1");
+        assert_eq!(add(&mut store, "1 a 2").unwrap().added, 3);
+        assert_eq!(store.last_added(), 3);
+    }
+
+    #[test]
+    fn duplicate_ids_are_ignored() {
+        let mut store = MemoryStore::default();
+        assert!(add(&mut store, "1 a 0").is_some());
+        assert!(add(&mut store, "1 a 0").is_none());
+        assert_eq!(store.last_added(), 1);
+        assert_eq!(store.since(0).len(), 1);
+    }
+
+    #[test]
+    fn since_is_ordered_and_exclusive() {
+        let mut store = MemoryStore::default();
+        for id in &["3 a 0", "1 a 0", "2 a 0"] {
+            add(&mut store, id);
+        }
+        assert_eq!(ids(store.since(0)), vec!["3 a 0", "1 a 0", "2 a 0"]);
+        assert_eq!(ids(store.since(1)), vec!["1 a 0", "2 a 0"]);
+        assert!(store.since(3).is_empty());
+    }
+
+    #[test]
+    fn remove_an_entry() {
+        let mut store = MemoryStore::default();
+        add(&mut store, "1 a 0");
+        add(&mut store, "1 a 1");
+
+        assert_eq!(store.remove("1 a 0").unwrap().added, 1);
+        assert!(store.remove("1 a 0").is_none());
+        assert!(!store.has("1 a 0"));
+        assert!(store.get("1 a 0").is_none());
+        assert_eq!(ids(store.since(0)), vec!["1 a 1"]);
+    }
+
+    #[test]
+    fn change_meta_keeps_id_and_added() {
+        let mut store = MemoryStore::default();
+        add(&mut store, "1 a 0");
+
+        let diff = json!({ "id": "2 b 0", "added": 10, "reasons": ["user"], "tab": 1 });
+        assert!(store.change_meta("1 a 0", diff.as_object().unwrap().clone()));
+        let entry = store.get("1 a 0").unwrap();
+        assert_eq!(entry.meta.id, "1 a 0");
+        assert_eq!(entry.meta.added, Some(1));
+        assert_eq!(entry.meta.reasons, vec!["user"]);
+        assert_eq!(entry.meta.extra["tab"], json!(1));
+        assert!(!store.has("2 b 0"));
+
+        assert!(!store.change_meta("2 b 0", Map::new()));
+    }
+}
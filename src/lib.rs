@@ -0,0 +1,21 @@
+//! Logux server on top of actix-web: clients connect through websockets and
+//! synchronise their actions with the server log.
+
+#[macro_use]
+extern crate log;
+
+pub mod broadcaster;
+pub mod channel;
+pub mod connection;
+pub mod domain;
+pub mod handler;
+pub mod infrastructure;
+mod middleware;
+pub mod server;
+
+pub use connection::MyWs;
+pub use domain::action::{Action, Meta};
+pub use domain::messages::error::LoguxError;
+pub use domain::messages::message::LoguxMessage;
+pub use domain::store::{LogEntry, SharedStore, Store};
+pub use server::builder::{LoguxServer, LoguxServerBuilder};
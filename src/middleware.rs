@@ -1,16 +1,22 @@
 use actix_web_actors::ws;
 
+use crate::connection::MyWs;
+use crate::domain::action::{Action, Meta};
+use crate::domain::client::ClientInfo;
 use crate::domain::messages::connect::ConnectMessage;
 use crate::domain::messages::connected::ConnectedMessage;
 use crate::domain::messages::ping::PingMessage;
 use crate::domain::messages::pong::PongMessage;
 use crate::domain::messages::sync::SyncMessage;
-use crate::MyWs;
+use crate::domain::store::LogEntry;
+use crate::domain::time::{now_ms, server_time, time_shift};
+use crate::handler::UndoReason;
+use actix::fut::{self, ActorFuture};
+use actix::AsyncContext;
+use serde_json::Value;
 /*
-use domain::messages::error::{UnkownMessageErrorMessage, WrongFormatErrorMessage};
 use domain::messages::sync::SyncMessage;
 use domain::messages::synced::SyncedMessage;
-use serde_json::Value;
 */
 
 pub fn middleware_connect(_ctx: &mut ws::WebsocketContext<MyWs>, _msg: &ConnectMessage) {
@@ -29,12 +35,218 @@ pub fn middleware_ping(_ctx: &mut ws::WebsocketContext<MyWs>, _msg: &PingMessage
     // ctx.text(serde_json::to_string(&vec!["test"]).unwrap());
 }
 
-pub fn middleware_sync(_ctx: &mut ws::WebsocketContext<MyWs>, _msg: &SyncMessage) {
+/// Check every action of a sync message with its type handler, then add it to
+/// the server log and process it.
+pub fn middleware_sync(act: &mut MyWs, ctx: &mut ws::WebsocketContext<MyWs>, msg: &SyncMessage) {
     info!("Sync middleware on");
-    let actions_iter = _msg.actions.chunks_exact(2);
-    for x in actions_iter {
-        info!("{:?}", x[0]);
-        info!("{:?}", x[1]);
+    let client = match &act.client {
+        Some(client) => client.clone(),
+        None => return,
+    };
+    // Measured once on the first sync, later actions can't move the shift.
+    let received = now_ms();
+    let shift = *act.time_shift.get_or_insert_with(|| {
+        msg.actions
+            .iter()
+            .map(|(_, meta)| time_shift(meta.time, received))
+            .max()
+            .unwrap_or(0)
+    });
+
+    for (action, meta) in &msg.actions {
+        // Receivers and reasons are decided by the server, only the handler
+        // `resend` can add receivers to a client action.
+        let mut meta = meta.for_client();
+        // Log times are in server clock.
+        meta.time = server_time(meta.time, shift);
+        // Actions without subprotocol were created with the client one.
+        if meta.subprotocol.is_none() {
+            meta.subprotocol = Some(client.subprotocol.to_string());
+        }
+
+        match action.action_type.as_str() {
+            "logux/subscribe" => {
+                subscribe(act, ctx, &client, action, meta);
+                continue;
+            }
+            "logux/unsubscribe" => {
+                unsubscribe(act, &client, action, meta);
+                continue;
+            }
+            _ => {}
+        }
+
+        let server = act.server.clone();
+        let handler = match server.handler(&action.action_type) {
+            Some(handler) => handler,
+            None => {
+                warn!(
+                    "Action {} has an unknown type {}",
+                    meta.id, action.action_type
+                );
+                act.undo(action, &meta, UndoReason::UnknownType);
+                continue;
+            }
+        };
+
+        if !handler.access(&client, action, &meta) {
+            warn!("Action {} denied for {}", meta.id, client.node_id);
+            act.undo(action, &meta, UndoReason::Denied);
+            continue;
+        }
+        if let Some(resend) = handler.resend(&client, action, &meta) {
+            resend.apply(&mut meta);
+        }
+
+        let id = meta.id.clone();
+        let entry = server.store.lock().unwrap().add(action.clone(), meta);
+        match entry {
+            Some(entry) => {
+                debug!(
+                    "Action {:?} added to the log ({})",
+                    entry.action, entry.added
+                );
+                act.broadcast(vec![entry.clone()]);
+                let process = handler.process(&client, &entry.action, &entry.meta);
+                ctx.spawn(
+                    fut::wrap_future(process).then(move |result, act: &mut MyWs, ctx| {
+                        match result {
+                            Ok(()) => act.processed(&entry.meta),
+                            Err(e) => {
+                                error!("Action {} processing failed: {}", id, e);
+                                act.send_debug_error(ctx, &e);
+                                act.undo(&entry.action, &entry.meta, UndoReason::Error);
+                            }
+                        }
+                        fut::ok(())
+                    }),
+                );
+            }
+            None => warn!("Action {} ignored, already in the log", id),
+        }
     }
-    // ctx.text(serde_json::to_string(&vec!["test"]).unwrap());
+}
+
+/// Subscribe the client to the channel of a `logux/subscribe` action, then
+/// send it the channel current state. A client subscribing again sends in
+/// `since` the time of the last action it got, the channel actions added after
+/// it are sent back.
+fn subscribe(
+    act: &mut MyWs,
+    ctx: &mut ws::WebsocketContext<MyWs>,
+    client: &ClientInfo,
+    action: &Action,
+    meta: Meta,
+) {
+    let server = act.server.clone();
+    let channel = match action.payload.get("channel").and_then(Value::as_str) {
+        Some(channel) => channel.to_string(),
+        None => {
+            warn!("Action {} has no channel", meta.id);
+            act.undo(action, &meta, UndoReason::WrongChannel);
+            return;
+        }
+    };
+    let (handler, params) = match server.channel(&channel) {
+        Some(found) => found,
+        None => {
+            warn!("Channel {} doesn't exist", channel);
+            act.undo(action, &meta, UndoReason::WrongChannel);
+            return;
+        }
+    };
+    if !handler.access(client, &params, action, &meta) {
+        warn!("Channel {} denied for {}", channel, client.node_id);
+        act.undo(action, &meta, UndoReason::Denied);
+        return;
+    }
+
+    let entry = match server
+        .store
+        .lock()
+        .unwrap()
+        .add(action.clone(), meta.clone())
+    {
+        Some(entry) => entry,
+        None => {
+            warn!("Action {} ignored, already in the log", meta.id);
+            return;
+        }
+    };
+    server.subscribe(act.connection_id, &channel);
+    info!("{} subscribed to {}", client.node_id, channel);
+
+    if let Some(since) = action
+        .payload
+        .get("since")
+        .and_then(|since| since["time"].as_u64())
+    {
+        let connection = act.connection_id;
+        let missed: Vec<LogEntry> = server
+            .store
+            .lock()
+            .unwrap()
+            .since(0)
+            .into_iter()
+            .filter(|entry| {
+                entry.meta.time > since
+                    && entry.meta.channels.contains(&channel)
+                    && server.is_receiver(client, connection, &entry.action, &entry.meta)
+            })
+            .collect();
+        if !missed.is_empty() {
+            info!(
+                "Resend {} {} actions to {}",
+                missed.len(),
+                channel,
+                client.node_id
+            );
+            act.send_sync(ctx, missed);
+        }
+    }
+
+    let node_id = client.node_id.clone();
+    let load = handler.load(client, &params, &entry.action, &entry.meta);
+    ctx.spawn(
+        fut::wrap_future(load).then(move |result, act: &mut MyWs, ctx| {
+            match result {
+                Ok(actions) => {
+                    let mut store = act.server.store.lock().unwrap();
+                    let entries: Vec<LogEntry> = actions
+                        .into_iter()
+                        .filter_map(|action| {
+                            let mut meta = act.server.meta();
+                            meta.nodes.push(node_id.clone());
+                            store.add(action, meta)
+                        })
+                        .collect();
+                    drop(store);
+                    act.broadcast(entries);
+                    act.processed(&entry.meta);
+                }
+                Err(e) => {
+                    error!("Channel {} loading failed: {}", channel, e);
+                    act.server.unsubscribe(act.connection_id, &channel);
+                    act.send_debug_error(ctx, &e);
+                    act.undo(&entry.action, &entry.meta, UndoReason::Error);
+                }
+            }
+            fut::ok(())
+        }),
+    );
+}
+
+/// Remove the client subscription to the channel of a `logux/unsubscribe` action.
+fn unsubscribe(act: &mut MyWs, client: &ClientInfo, action: &Action, meta: Meta) {
+    let channel = match action.payload.get("channel").and_then(Value::as_str) {
+        Some(channel) => channel.to_string(),
+        None => {
+            warn!("Action {} has no channel", meta.id);
+            act.undo(action, &meta, UndoReason::WrongChannel);
+            return;
+        }
+    };
+    act.server.unsubscribe(act.connection_id, &channel);
+    info!("{} unsubscribed from {}", client.node_id, channel);
+    act.processed(&meta);
 }
@@ -0,0 +1,307 @@
+use crate::channel::{ChannelHandler, ChannelParams, ChannelPattern};
+use crate::domain::action::{Action, Meta};
+use crate::domain::action_id::{ActionId, ActionIdGenerator};
+use crate::domain::client::ClientInfo;
+use crate::domain::messages::connected::OptionnalConnectedMessage;
+use crate::domain::node_id::generate_node_id;
+use crate::domain::store::SharedStore;
+use crate::domain::time::now_ms;
+use crate::handler::ActionHandler;
+use futures::Future;
+use semver::{Version, VersionReq};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub mod builder;
+
+pub use self::builder::{LoguxServer, LoguxServerBuilder};
+
+/// Logux protocol version spoken by the server.
+pub const PROTOCOL_VERSION: u64 = 4;
+
+/// Oldest Logux protocol version the server accepts from clients.
+pub const MIN_PROTOCOL_VERSION: u64 = 3;
+
+/// Authentication result, `Ok(false)` on wrong credentials.
+pub type AuthFuture = Box<dyn Future<Item = bool, Error = String>>;
+
+/// Check the credentials of a client, from its user id, credentials and informations.
+pub type Authenticator =
+    Box<dyn Fn(Option<&str>, Option<&Value>, &ClientInfo) -> AuthFuture + Send + Sync>;
+
+/// Identify a live connection, a client node may have several over time.
+pub type ConnectionId = u64;
+
+/// Default delay without messages before pinging a client.
+pub const PING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Default delay to wait for a `pong` before closing the connection.
+pub const PING_TIMEOUT: Duration = Duration::from_secs(70);
+
+/// Called with the text of `["debug", "error", text]` messages sent by clients.
+pub type ClientErrorHook = Box<dyn Fn(&ClientInfo, &str) + Send + Sync>;
+
+/// Where the server runs, development servers send their errors to clients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Environment {
+    Development,
+    Production,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            Environment::Development
+        } else {
+            Environment::Production
+        }
+    }
+}
+
+/// Settings of the Logux server.
+pub struct ServerConfig {
+    /// Server node id, used in `connected` messages, action ids and logs.
+    pub node_id: String,
+    /// Protocol version sent in `connected`.
+    pub protocol: u64,
+    /// Clients with an older protocol version get a `wrong-protocol` error.
+    pub min_protocol: u64,
+    /// Version of the application subprotocol, built on top of Logux.
+    pub subprotocol: Version,
+    /// Client subprotocol versions accepted by the server, like `>= 1.2.0`.
+    pub supports: VersionReq,
+    /// Server credentials, sent to clients in `connected`.
+    pub credentials: Option<Value>,
+    /// Extra keys of the `connected` options.
+    pub connected_options: Map<String, Value>,
+    /// Server headers, sent to clients after `connected` if not empty.
+    pub headers: Map<String, Value>,
+    /// Called on every `connect`, every client is accepted without it.
+    pub authenticator: Option<Authenticator>,
+    /// Idle clients are pinged after this delay.
+    pub ping_interval: Duration,
+    /// Clients which don't answer a ping in this delay get a `timeout` error.
+    pub timeout: Duration,
+    pub environment: Environment,
+    /// Called on client errors, they are only logged without it.
+    pub on_client_error: Option<ClientErrorHook>,
+}
+
+impl ServerConfig {
+    /// Check if a client protocol version is supported.
+    pub fn supports_protocol(&self, protocol: u64) -> bool {
+        protocol >= self.min_protocol
+    }
+
+    /// Options sent to clients in `connected`.
+    pub fn connected_options(&self) -> OptionnalConnectedMessage {
+        OptionnalConnectedMessage {
+            subprotocol: Some(self.subprotocol.to_string()),
+            credentials: self.credentials.clone(),
+            extra: self.connected_options.clone(),
+        }
+    }
+
+    /// Parse a client subprotocol version, `None` if it is not supported.
+    /// Clients without subprotocol are considered to use `0.0.0`.
+    pub fn supports_subprotocol(&self, subprotocol: Option<&str>) -> Option<Version> {
+        Version::parse(subprotocol.unwrap_or("0.0.0"))
+            .ok()
+            .filter(|version| self.supports.matches(version))
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            node_id: generate_node_id(None),
+            protocol: PROTOCOL_VERSION,
+            min_protocol: MIN_PROTOCOL_VERSION,
+            subprotocol: Version::new(0, 0, 0),
+            supports: VersionReq::any(),
+            credentials: None,
+            connected_options: Map::new(),
+            headers: Map::new(),
+            authenticator: None,
+            ping_interval: PING_INTERVAL,
+            timeout: PING_TIMEOUT,
+            environment: Environment::default(),
+            on_client_error: None,
+        }
+    }
+}
+
+/// State shared between every connection of the server.
+pub struct Server {
+    pub config: ServerConfig,
+    /// Server action log.
+    pub store: SharedStore,
+    id_generator: Mutex<ActionIdGenerator>,
+    /// Last `added` acknowledged by each client node with `synced`.
+    delivered: Mutex<HashMap<String, u64>>,
+    /// Last id given to a connection.
+    last_connection: AtomicU64,
+    /// Channels each live connection subscribed to.
+    subscriptions: Mutex<HashMap<ConnectionId, HashSet<String>>>,
+    /// Handlers by action type.
+    handlers: HashMap<String, Box<dyn ActionHandler>>,
+    /// Handler for action types without their own handler.
+    other_handler: Option<Box<dyn ActionHandler>>,
+    /// Channel handlers, the first matching pattern is used.
+    channels: Vec<(ChannelPattern, Box<dyn ChannelHandler>)>,
+}
+
+impl Server {
+    pub fn new(config: ServerConfig, store: SharedStore) -> Server {
+        let id_generator = Mutex::new(ActionIdGenerator::new(&config.node_id));
+        Server {
+            config,
+            store,
+            id_generator,
+            delivered: Mutex::new(HashMap::new()),
+            last_connection: AtomicU64::new(0),
+            subscriptions: Mutex::new(HashMap::new()),
+            handlers: HashMap::new(),
+            other_handler: None,
+            channels: Vec::new(),
+        }
+    }
+
+    /// Define how actions of a type, like `user/rename`, are handled.
+    pub fn add_type<H>(&mut self, action_type: &str, handler: H)
+    where
+        H: ActionHandler + 'static,
+    {
+        self.handlers
+            .insert(action_type.to_string(), Box::new(handler));
+    }
+
+    /// Define how actions without their own handler are handled.
+    /// Without it, they are refused.
+    pub fn other_type<H>(&mut self, handler: H)
+    where
+        H: ActionHandler + 'static,
+    {
+        self.other_handler = Some(Box::new(handler));
+    }
+
+    /// Handler for an action type.
+    pub fn handler(&self, action_type: &str) -> Option<&dyn ActionHandler> {
+        self.handlers
+            .get(action_type)
+            .or(self.other_handler.as_ref())
+            .map(|handler| handler.as_ref())
+    }
+
+    /// Define which channels, like `user/:id`, clients can subscribe to.
+    pub fn add_channel<H>(&mut self, pattern: &str, handler: H)
+    where
+        H: ChannelHandler + 'static,
+    {
+        self.channels
+            .push((ChannelPattern::new(pattern), Box::new(handler)));
+    }
+
+    /// Handler and params of a channel, `None` if no pattern matches it.
+    pub fn channel(&self, channel: &str) -> Option<(&dyn ChannelHandler, ChannelParams)> {
+        self.channels.iter().find_map(|(pattern, handler)| {
+            pattern
+                .matches(channel)
+                .map(|params| (handler.as_ref(), params))
+        })
+    }
+
+    /// Save that a client node received every action up to `synced`.
+    pub fn acknowledge(&self, node_id: &str, synced: u64) {
+        let mut delivered = self.delivered.lock().unwrap();
+        let last = delivered.entry(node_id.to_string()).or_insert(0);
+        *last = synced.max(*last);
+    }
+
+    /// Last `added` acknowledged by a client node, 0 if it never acknowledged anything.
+    pub fn delivered(&self, node_id: &str) -> u64 {
+        self.delivered
+            .lock()
+            .unwrap()
+            .get(node_id)
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Id for a new connection, never reused. A client reconnecting with the
+    /// same node id gets a new one.
+    pub fn connection_id(&self) -> ConnectionId {
+        self.last_connection.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Channels a connection subscribed to.
+    pub fn subscriptions(&self, connection: ConnectionId) -> HashSet<String> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .get(&connection)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn subscribe(&self, connection: ConnectionId, channel: &str) {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .entry(connection)
+            .or_default()
+            .insert(channel.to_string());
+    }
+
+    pub fn unsubscribe(&self, connection: ConnectionId, channel: &str) {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if let Some(channels) = subscriptions.get_mut(&connection) {
+            channels.remove(channel);
+            if channels.is_empty() {
+                subscriptions.remove(&connection);
+            }
+        }
+    }
+
+    /// Forget every subscription of a closed connection.
+    pub fn unsubscribe_all(&self, connection: ConnectionId) {
+        self.subscriptions.lock().unwrap().remove(&connection);
+    }
+
+    /// Check if a client should receive an action: it is sent to the client
+    /// itself, or to one of the connection channels which doesn't filter it out.
+    pub fn is_receiver(
+        &self,
+        client: &ClientInfo,
+        connection: ConnectionId,
+        action: &Action,
+        meta: &Meta,
+    ) -> bool {
+        if client.is_receiver(meta, &HashSet::new()) {
+            return true;
+        }
+        let channels = self.subscriptions(connection);
+        client.is_receiver(meta, &channels)
+            && meta
+                .channels
+                .iter()
+                .filter(|channel| channels.contains(*channel))
+                .any(|channel| match self.channel(channel) {
+                    Some((handler, params)) => handler.filter(client, &params, action, meta),
+                    None => true,
+                })
+    }
+
+    /// New meta for an action created by the server.
+    pub fn meta(&self) -> Meta {
+        Meta::new(self.generate_id().to_string(), now_ms())
+    }
+
+    /// Id for a new action created by the server.
+    pub fn generate_id(&self) -> ActionId {
+        self.id_generator.lock().unwrap().next_id()
+    }
+}
@@ -0,0 +1,259 @@
+use super::{AuthFuture, Environment, Server, ServerConfig};
+use crate::broadcaster::Broadcaster;
+use crate::channel::{ChannelHandler, ChannelPattern};
+use crate::connection::index;
+use crate::domain::client::ClientInfo;
+use crate::domain::node_id::generate_node_id;
+use crate::domain::store::SharedStore;
+use crate::handler::ActionHandler;
+use crate::infrastructure::memory_store::MemoryStore;
+use actix::{Actor, Addr, System};
+use actix_web::{web, App, HttpServer, Resource};
+use semver::{Version, VersionReq};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Logux server ready to run, alone or inside an existing actix-web `App`.
+#[derive(Clone)]
+pub struct LoguxServer {
+    pub server: Arc<Server>,
+    pub host: String,
+    pub port: u16,
+    /// Path of the websocket endpoint, like `/ws/`.
+    pub path: String,
+    /// Listen with `wss://` instead of `ws://`.
+    pub tls: Option<rustls::ServerConfig>,
+}
+
+impl LoguxServer {
+    pub fn builder() -> LoguxServerBuilder {
+        LoguxServerBuilder::default()
+    }
+
+    /// Start the registry of live connections. It needs a running actix
+    /// system and must be shared by every worker of the HTTP server.
+    pub fn start_broadcaster(&self) -> Addr<Broadcaster> {
+        Broadcaster::new(self.server.clone()).start()
+    }
+
+    /// Websocket endpoint at the server path, to mount in an `App` with
+    /// `App::service`.
+    pub fn service(&self, broadcaster: Addr<Broadcaster>) -> Resource {
+        web::resource(self.path.as_str())
+            .data(self.server.clone())
+            .data(broadcaster)
+            .route(web::get().to(index))
+    }
+
+    /// Listen on the server host and port until the process is stopped.
+    pub fn run(self) -> io::Result<()> {
+        let system = System::new("logux");
+        let broadcaster = self.start_broadcaster();
+        let address = (self.host.clone(), self.port);
+        let tls = self.tls.clone();
+
+        info!("Starting logtux-rust as {}", self.server.config.node_id);
+        info!(
+            "Listening to {}://{}:{}{}",
+            if tls.is_some() { "wss" } else { "ws" },
+            self.host,
+            self.port,
+            self.path
+        );
+        let http = HttpServer::new(move || App::new().service(self.service(broadcaster.clone())));
+        match tls {
+            Some(tls) => http.bind_rustls(address, tls)?,
+            None => http.bind(address)?,
+        }
+        .start();
+        system.run()
+    }
+}
+
+/// Settings and handlers of a `LoguxServer`, every setting has a default.
+pub struct LoguxServerBuilder {
+    config: ServerConfig,
+    store: Option<SharedStore>,
+    host: String,
+    port: u16,
+    path: String,
+    tls: Option<rustls::ServerConfig>,
+    handlers: HashMap<String, Box<dyn ActionHandler>>,
+    other_handler: Option<Box<dyn ActionHandler>>,
+    channels: Vec<(ChannelPattern, Box<dyn ChannelHandler>)>,
+}
+
+impl Default for LoguxServerBuilder {
+    fn default() -> Self {
+        LoguxServerBuilder {
+            config: ServerConfig::default(),
+            store: None,
+            host: String::from("127.0.0.1"),
+            port: 8088,
+            path: String::from("/ws/"),
+            tls: None,
+            handlers: HashMap::new(),
+            other_handler: None,
+            channels: Vec::new(),
+        }
+    }
+}
+
+impl LoguxServerBuilder {
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = host.to_string();
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.to_string();
+        self
+    }
+
+    /// Certificates used to listen with TLS.
+    pub fn tls(mut self, tls: rustls::ServerConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    pub fn node_id(mut self, node_id: &str) -> Self {
+        self.config.node_id = node_id.to_string();
+        self
+    }
+
+    /// Random node id with this prefix instead of `server`, like `<prefix>:<random>`.
+    pub fn node_id_prefix(mut self, prefix: &str) -> Self {
+        self.config.node_id = generate_node_id(Some(prefix));
+        self
+    }
+
+    /// Version of the application subprotocol.
+    pub fn subprotocol(mut self, subprotocol: Version) -> Self {
+        self.config.subprotocol = subprotocol;
+        self
+    }
+
+    /// Client subprotocol versions accepted by the server.
+    pub fn supports(mut self, supports: VersionReq) -> Self {
+        self.config.supports = supports;
+        self
+    }
+
+    /// Oldest Logux protocol version accepted from clients.
+    pub fn min_protocol(mut self, min_protocol: u64) -> Self {
+        self.config.min_protocol = min_protocol;
+        self
+    }
+
+    /// Server credentials, sent to clients in `connected`.
+    pub fn credentials(mut self, credentials: Value) -> Self {
+        self.config.credentials = Some(credentials);
+        self
+    }
+
+    /// Extra keys of the `connected` options.
+    pub fn connected_options(mut self, options: Map<String, Value>) -> Self {
+        self.config.connected_options = options;
+        self
+    }
+
+    /// Server headers, sent to clients after `connected`.
+    pub fn headers(mut self, headers: Map<String, Value>) -> Self {
+        self.config.headers = headers;
+        self
+    }
+
+    pub fn ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.config.ping_interval = ping_interval;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.config.environment = environment;
+        self
+    }
+
+    /// Check the credentials of every client, see `ServerConfig::authenticator`.
+    pub fn auth<F>(mut self, authenticator: F) -> Self
+    where
+        F: Fn(Option<&str>, Option<&Value>, &ClientInfo) -> AuthFuture + Send + Sync + 'static,
+    {
+        self.config.authenticator = Some(Box::new(authenticator));
+        self
+    }
+
+    /// Called with the errors sent by clients, see `ServerConfig::on_client_error`.
+    pub fn on_client_error<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ClientInfo, &str) + Send + Sync + 'static,
+    {
+        self.config.on_client_error = Some(Box::new(hook));
+        self
+    }
+
+    /// Action log, an in-memory one is used without it.
+    pub fn store(mut self, store: SharedStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// See `Server::add_type`.
+    pub fn add_type<H>(mut self, action_type: &str, handler: H) -> Self
+    where
+        H: ActionHandler + 'static,
+    {
+        self.handlers
+            .insert(action_type.to_string(), Box::new(handler));
+        self
+    }
+
+    /// See `Server::other_type`.
+    pub fn other_type<H>(mut self, handler: H) -> Self
+    where
+        H: ActionHandler + 'static,
+    {
+        self.other_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// See `Server::add_channel`.
+    pub fn add_channel<H>(mut self, pattern: &str, handler: H) -> Self
+    where
+        H: ChannelHandler + 'static,
+    {
+        self.channels
+            .push((ChannelPattern::new(pattern), Box::new(handler)));
+        self
+    }
+
+    pub fn build(self) -> LoguxServer {
+        let store = self
+            .store
+            .unwrap_or_else(|| Arc::new(Mutex::new(MemoryStore::default())));
+        let mut server = Server::new(self.config, store);
+        server.handlers = self.handlers;
+        server.other_handler = self.other_handler;
+        server.channels = self.channels;
+
+        LoguxServer {
+            server: Arc::new(server),
+            host: self.host,
+            port: self.port,
+            path: self.path,
+            tls: self.tls,
+        }
+    }
+}